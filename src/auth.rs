@@ -0,0 +1,106 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use crate::config::Config;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+const ARGON2_PREFIX: &str = "$argon2";
+
+fn hasher(config: &Config) -> Result<Argon2<'static>, String> {
+    let params = Params::new(config.argon2_memory_kib, config.argon2_iterations, config.argon2_parallelism, None)
+        .map_err(|e| format!("Invalid Argon2 parameters: {}", e))?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+pub fn hash_password(password: &str, config: &Config) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    hasher(config)?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash password: {}", e))
+}
+
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    if !stored.starts_with(ARGON2_PREFIX) {
+        // Accounts created before hashing was introduced still hold the raw password.
+        return constant_time_eq(stored, password);
+    }
+
+    match PasswordHash::new(stored) {
+        Ok(parsed) => Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Compares two secrets without leaking where they differ. Both sides are hashed first so
+/// the comparison doesn't leak the length of the expected value either.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    Sha256::digest(a.as_bytes()).ct_eq(&Sha256::digest(b.as_bytes())).into()
+}
+
+pub fn generate_refresh_token() -> String {
+    let bytes: [u8; 32] = rand::random();
+    to_hex(&bytes)
+}
+
+pub fn hash_refresh_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn needs_rehash(stored: &str, config: &Config) -> bool {
+    let parsed = match PasswordHash::new(stored) {
+        Ok(parsed) => parsed,
+        Err(_) => return true,
+    };
+    if parsed.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+
+    match Params::try_from(&parsed) {
+        Ok(params) => {
+            params.m_cost() < config.argon2_memory_kib
+                || params.t_cost() < config.argon2_iterations
+                || params.p_cost() < config.argon2_parallelism
+        },
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config { argon2_memory_kib: 64, argon2_iterations: 1, ..Config::default() }
+    }
+
+    #[test]
+    fn legacy_plaintext_passwords_still_verify() {
+        assert!(verify_password("hunter22", "hunter22"));
+        assert!(!verify_password("hunter2", "hunter22"));
+        assert!(!verify_password("", "hunter22"));
+        assert!(needs_rehash("hunter22", &config()));
+    }
+
+    #[test]
+    fn argon2_hashes_verify_and_are_current() {
+        let config = config();
+        let hash = hash_password("hunter22", &config).unwrap();
+        assert!(verify_password("hunter22", &hash));
+        assert!(!verify_password("hunter2", &hash));
+        assert!(!verify_password(&hash, &hash));
+        assert!(!needs_rehash(&hash, &config));
+    }
+
+    #[test]
+    fn constant_time_eq_matches_only_equal_strings() {
+        assert!(constant_time_eq("token", "token"));
+        assert!(!constant_time_eq("token", "tokem"));
+        assert!(!constant_time_eq("token", "token2"));
+    }
+}
@@ -0,0 +1,251 @@
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use crate::models::{Page, PageQuery, DEFAULT_MAX_PAGE_SIZE, DEFAULT_MAX_SALARY, DEFAULT_PAGE_SIZE};
+use crate::lockout::parse_allowlist;
+use crate::proxy::parse_trusted_proxies;
+use ipnet::IpNet;
+use std::env;
+use std::net::IpAddr;
+use std::str::FromStr;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub session_ttl_secs: i64,
+    pub session_sliding: bool,
+    pub session_max_lifetime_secs: i64,
+    pub refresh_ttl_secs: i64,
+    pub max_sessions_per_client: u32,
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub admin_token: Option<String>,
+    pub workers: Option<usize>,
+    pub keep_alive_secs: Option<u64>,
+    pub trusted_proxies: Vec<IpAddr>,
+    pub max_salary: f64,
+    pub log_filter: String,
+    pub reminder_poll_secs: u64,
+    pub event_timezone: Tz,
+    pub db_read_replica: bool,
+    pub default_page_size: u32,
+    pub max_page_size: u32,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+    pub login_max_failures: u32,
+    pub login_lockout_secs: i64,
+    pub login_allowlist: Vec<IpNet>,
+    pub password_history: u32,
+    pub sandbox_mode: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            session_ttl_secs: 60 * 60 * 24,
+            session_sliding: false,
+            session_max_lifetime_secs: 60 * 60 * 24 * 30,
+            refresh_ttl_secs: 60 * 60 * 24 * 30,
+            max_sessions_per_client: 10,
+            argon2_memory_kib: 19 * 1024,
+            argon2_iterations: 2,
+            argon2_parallelism: 1,
+            admin_token: None,
+            workers: None,
+            keep_alive_secs: None,
+            trusted_proxies: Vec::new(),
+            max_salary: DEFAULT_MAX_SALARY,
+            log_filter: "info".to_string(),
+            reminder_poll_secs: 60,
+            event_timezone: Tz::UTC,
+            db_read_replica: false,
+            default_page_size: DEFAULT_PAGE_SIZE,
+            max_page_size: DEFAULT_MAX_PAGE_SIZE,
+            tls_cert: None,
+            tls_key: None,
+            login_max_failures: 5,
+            login_lockout_secs: 15 * 60,
+            login_allowlist: Vec::new(),
+            password_history: 5,
+            sandbox_mode: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, String> {
+        let defaults = Self::default();
+
+        let config = Self {
+            session_ttl_secs: env_or("QADS_SESSION_TTL_SECS", defaults.session_ttl_secs)?,
+            session_sliding: env_or("QADS_SESSION_SLIDING", defaults.session_sliding)?,
+            session_max_lifetime_secs: env_or("QADS_SESSION_MAX_LIFETIME_SECS", defaults.session_max_lifetime_secs)?,
+            refresh_ttl_secs: env_or("QADS_REFRESH_TTL_SECS", defaults.refresh_ttl_secs)?,
+            max_sessions_per_client: env_or("QADS_MAX_SESSIONS_PER_CLIENT", defaults.max_sessions_per_client)?,
+            argon2_memory_kib: env_or("QADS_ARGON2_MEMORY_KIB", defaults.argon2_memory_kib)?,
+            argon2_iterations: env_or("QADS_ARGON2_ITERATIONS", defaults.argon2_iterations)?,
+            argon2_parallelism: env_or("QADS_ARGON2_PARALLELISM", defaults.argon2_parallelism)?,
+            admin_token: env::var("QADS_ADMIN_TOKEN").ok().filter(|t| !t.trim().is_empty()),
+            workers: env_opt("QADS_WORKERS")?,
+            keep_alive_secs: env_opt("QADS_KEEPALIVE_SECS")?,
+            trusted_proxies: parse_trusted_proxies(&env::var("QADS_TRUSTED_PROXIES").unwrap_or_default())?,
+            max_salary: env_or("QADS_MAX_SALARY", defaults.max_salary)?,
+            log_filter: env::var("RUST_LOG")
+                .or_else(|_| env::var("QADS_LOG_LEVEL"))
+                .ok()
+                .filter(|f| !f.trim().is_empty())
+                .unwrap_or(defaults.log_filter),
+            reminder_poll_secs: env_or("QADS_REMINDER_POLL_SECS", defaults.reminder_poll_secs)?,
+            event_timezone: env_or("QADS_EVENT_TIMEZONE", defaults.event_timezone)?,
+            db_read_replica: env_or("QADS_DB_READ_REPLICA", defaults.db_read_replica)?,
+            default_page_size: env_or("QADS_DEFAULT_PAGE_SIZE", defaults.default_page_size)?,
+            max_page_size: env_or("QADS_MAX_PAGE_SIZE", defaults.max_page_size)?,
+            tls_cert: env::var("QADS_TLS_CERT").ok().filter(|p| !p.trim().is_empty()),
+            tls_key: env::var("QADS_TLS_KEY").ok().filter(|p| !p.trim().is_empty()),
+            login_max_failures: env_or("QADS_LOGIN_MAX_FAILURES", defaults.login_max_failures)?,
+            login_lockout_secs: env_or("QADS_LOGIN_LOCKOUT_SECS", defaults.login_lockout_secs)?,
+            login_allowlist: parse_allowlist(&env::var("QADS_LOGIN_ALLOWLIST").unwrap_or_default())?,
+            password_history: env_or("QADS_PASSWORD_HISTORY", defaults.password_history)?,
+            sandbox_mode: env_or("QADS_SANDBOX_MODE", defaults.sandbox_mode)?,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.session_ttl_secs <= 0 {
+            return Err("QADS_SESSION_TTL_SECS must be positive".to_string());
+        }
+        if self.session_max_lifetime_secs < self.session_ttl_secs {
+            return Err("QADS_SESSION_MAX_LIFETIME_SECS must be at least QADS_SESSION_TTL_SECS".to_string());
+        }
+        if self.refresh_ttl_secs <= 0 {
+            return Err("QADS_REFRESH_TTL_SECS must be positive".to_string());
+        }
+        if self.max_sessions_per_client == 0 {
+            return Err("QADS_MAX_SESSIONS_PER_CLIENT must be at least 1".to_string());
+        }
+        if !self.max_salary.is_finite() || self.max_salary <= 0.0 {
+            return Err("QADS_MAX_SALARY must be a positive finite number".to_string());
+        }
+        if self.reminder_poll_secs == 0 {
+            return Err("QADS_REMINDER_POLL_SECS must be at least 1".to_string());
+        }
+        if self.default_page_size == 0 {
+            return Err("QADS_DEFAULT_PAGE_SIZE must be at least 1".to_string());
+        }
+        if self.max_page_size < self.default_page_size {
+            return Err("QADS_MAX_PAGE_SIZE must be at least QADS_DEFAULT_PAGE_SIZE".to_string());
+        }
+        if self.tls_cert.is_some() != self.tls_key.is_some() {
+            return Err("QADS_TLS_CERT and QADS_TLS_KEY must be set together".to_string());
+        }
+        if self.login_max_failures == 0 {
+            return Err("QADS_LOGIN_MAX_FAILURES must be at least 1".to_string());
+        }
+        if self.login_lockout_secs <= 0 {
+            return Err("QADS_LOGIN_LOCKOUT_SECS must be positive".to_string());
+        }
+        if self.workers == Some(0) {
+            return Err("QADS_WORKERS must be at least 1".to_string());
+        }
+        if let Err(e) = EnvFilter::try_new(&self.log_filter) {
+            return Err(format!("Invalid log filter {:?}: {}", self.log_filter, e));
+        }
+        if let Err(e) = argon2::Params::new(self.argon2_memory_kib, self.argon2_iterations, self.argon2_parallelism, None) {
+            return Err(format!("Invalid Argon2 parameters: {}", e));
+        }
+        Ok(())
+    }
+
+    pub fn page(&self, query: &PageQuery) -> Result<Page, String> {
+        query.resolve(self.default_page_size, self.max_page_size)
+    }
+
+    pub fn tls_paths(&self) -> Option<(&str, &str)> {
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => Some((cert, key)),
+            _ => None,
+        }
+    }
+
+    pub fn session_expiry(&self, created_at: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+        let expires_at = now + Duration::seconds(self.session_ttl_secs);
+        let cap = created_at + Duration::seconds(self.session_max_lifetime_secs);
+        expires_at.min(cap)
+    }
+}
+
+fn env_or<T: FromStr>(name: &str, default: T) -> Result<T, String> {
+    parse_var(name, env::var(name).ok(), default)
+}
+
+fn env_opt<T: FromStr>(name: &str) -> Result<Option<T>, String> {
+    parse_opt(name, env::var(name).ok())
+}
+
+fn parse_opt<T: FromStr>(name: &str, raw: Option<String>) -> Result<Option<T>, String> {
+    match raw {
+        Some(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("Invalid value for {}: {}", name, value)),
+        _ => Ok(None),
+    }
+}
+
+fn parse_var<T: FromStr>(name: &str, raw: Option<String>, default: T) -> Result<T, String> {
+    Ok(parse_opt(name, raw)?.unwrap_or(default))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_expiry_slides_until_max_lifetime() {
+        let config = Config { session_ttl_secs: 600, session_max_lifetime_secs: 1200, ..Config::default() };
+        let created_at = Utc::now();
+
+        let early = created_at + Duration::seconds(60);
+        assert_eq!(config.session_expiry(created_at, early), early + Duration::seconds(600));
+
+        let late = created_at + Duration::seconds(1000);
+        assert_eq!(config.session_expiry(created_at, late), created_at + Duration::seconds(1200));
+    }
+
+    #[test]
+    fn non_numeric_worker_settings_are_rejected() {
+        assert_eq!(
+            parse_opt::<usize>("QADS_WORKERS", Some("four".to_string())),
+            Err("Invalid value for QADS_WORKERS: four".to_string()),
+        );
+        assert!(parse_opt::<u64>("QADS_KEEPALIVE_SECS", Some("-5".to_string())).is_err());
+        assert_eq!(parse_opt::<usize>("QADS_WORKERS", Some(" 4 ".to_string())), Ok(Some(4)));
+        assert_eq!(parse_opt::<usize>("QADS_WORKERS", Some("  ".to_string())), Ok(None));
+        assert_eq!(parse_var("QADS_WORKERS", None, 2usize), Ok(2));
+    }
+
+    #[test]
+    fn validation_rejects_inconsistent_settings() {
+        assert!(Config::default().validate().is_ok());
+
+        let cert_only = Config { tls_cert: Some("cert.pem".to_string()), ..Config::default() };
+        assert_eq!(cert_only.validate(), Err("QADS_TLS_CERT and QADS_TLS_KEY must be set together".to_string()));
+        let both = Config { tls_key: Some("key.pem".to_string()), ..cert_only };
+        assert!(both.validate().is_ok());
+
+        let pages = Config { default_page_size: 100, max_page_size: 50, ..Config::default() };
+        assert_eq!(pages.validate(), Err("QADS_MAX_PAGE_SIZE must be at least QADS_DEFAULT_PAGE_SIZE".to_string()));
+        assert!(Config { workers: Some(0), ..Config::default() }.validate().is_err());
+        assert!(Config { log_filter: "qads=nonsense[".to_string(), ..Config::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn tls_paths_need_both_cert_and_key() {
+        assert_eq!(Config::default().tls_paths(), None);
+        let both = Config { tls_cert: Some("cert.pem".to_string()), tls_key: Some("key.pem".to_string()), ..Config::default() };
+        assert_eq!(both.tls_paths(), Some(("cert.pem", "key.pem")));
+    }
+}
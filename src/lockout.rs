@@ -0,0 +1,158 @@
+use chrono::{DateTime, Duration, Utc};
+use ipnet::IpNet;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+pub fn parse_allowlist(raw: &str) -> Result<Vec<IpNet>, String> {
+    raw.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<IpNet>()
+                .or_else(|_| s.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| format!("Invalid login allowlist entry: {}", s))
+        })
+        .collect()
+}
+
+// Upper bound on addresses tracked at once, so a spray of failed logins from many sources can't
+// grow the map without limit.
+const MAX_TRACKED_ADDRESSES: usize = 10_000;
+
+struct FailureRecord {
+    count: u32,
+    last_failure_at: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+impl FailureRecord {
+    // Failures older than the lockout window no longer count towards a lockout.
+    fn is_stale(&self, now: DateTime<Utc>, lockout: Duration) -> bool {
+        match self.locked_until {
+            Some(until) => until <= now,
+            None => self.last_failure_at + lockout <= now,
+        }
+    }
+}
+
+pub struct LoginLimiter {
+    max_failures: u32,
+    lockout: Duration,
+    allowlist: Vec<IpNet>,
+    max_tracked: usize,
+    failures: Mutex<HashMap<IpAddr, FailureRecord>>,
+}
+
+impl LoginLimiter {
+    pub fn new(max_failures: u32, lockout_secs: i64, allowlist: Vec<IpNet>) -> Self {
+        Self {
+            max_failures,
+            lockout: Duration::seconds(lockout_secs),
+            allowlist,
+            max_tracked: MAX_TRACKED_ADDRESSES,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_allowlisted(&self, ip: IpAddr) -> bool {
+        self.allowlist.iter().any(|net| net.contains(&ip))
+    }
+
+    pub fn locked_until(&self, ip: IpAddr, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.is_allowlisted(ip) {
+            return None;
+        }
+
+        let mut failures = self.failures.lock().unwrap();
+        match failures.get(&ip).and_then(|r| r.locked_until) {
+            Some(until) if until > now => Some(until),
+            Some(_) => {
+                failures.remove(&ip);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn record_failure(&self, ip: IpAddr, now: DateTime<Utc>) {
+        if self.is_allowlisted(ip) {
+            return;
+        }
+
+        let mut failures = self.failures.lock().unwrap();
+        if failures.get(&ip).is_some_and(|r| r.is_stale(now, self.lockout)) {
+            failures.remove(&ip);
+        }
+        if !failures.contains_key(&ip) && failures.len() >= self.max_tracked {
+            self.prune(&mut failures, now);
+        }
+
+        let record = failures.entry(ip).or_insert(FailureRecord { count: 0, last_failure_at: now, locked_until: None });
+        record.count += 1;
+        record.last_failure_at = now;
+        if record.count >= self.max_failures {
+            record.locked_until = Some(now + self.lockout);
+        }
+    }
+
+    pub fn record_success(&self, ip: IpAddr) {
+        self.failures.lock().unwrap().remove(&ip);
+    }
+
+    /// Drops stale records, then, if the map is still full, the one with the oldest failure.
+    fn prune(&self, failures: &mut HashMap<IpAddr, FailureRecord>, now: DateTime<Utc>) {
+        failures.retain(|_, record| !record.is_stale(now, self.lockout));
+        if failures.len() >= self.max_tracked {
+            if let Some(oldest) = failures.iter().min_by_key(|(_, r)| r.last_failure_at).map(|(ip, _)| *ip) {
+                failures.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([203, 0, 113, last])
+    }
+
+    #[test]
+    fn repeated_failures_lock_out_until_the_window_passes() {
+        let limiter = LoginLimiter::new(3, 60, Vec::new());
+        let now = Utc::now();
+        limiter.record_failure(ip(1), now);
+        limiter.record_failure(ip(1), now);
+        assert_eq!(limiter.locked_until(ip(1), now), None);
+
+        limiter.record_failure(ip(1), now);
+        assert_eq!(limiter.locked_until(ip(1), now), Some(now + Duration::seconds(60)));
+        assert_eq!(limiter.locked_until(ip(1), now + Duration::seconds(61)), None);
+    }
+
+    #[test]
+    fn allowlisted_addresses_are_never_locked() {
+        let limiter = LoginLimiter::new(1, 60, parse_allowlist("203.0.113.0/28").unwrap());
+        let now = Utc::now();
+        limiter.record_failure(ip(5), now);
+        limiter.record_failure(ip(200), now);
+        assert_eq!(limiter.locked_until(ip(5), now), None);
+        assert!(limiter.locked_until(ip(200), now).is_some());
+    }
+
+    #[test]
+    fn tracked_addresses_are_pruned_at_the_cap() {
+        let limiter = LoginLimiter { max_tracked: 2, ..LoginLimiter::new(5, 60, Vec::new()) };
+        let now = Utc::now();
+        limiter.record_failure(ip(1), now - Duration::seconds(120));
+        limiter.record_failure(ip(2), now - Duration::seconds(10));
+        limiter.record_failure(ip(3), now - Duration::seconds(5));
+        limiter.record_failure(ip(4), now);
+
+        let failures = limiter.failures.lock().unwrap();
+        assert_eq!(failures.len(), 2);
+        assert!(failures.contains_key(&ip(3)) && failures.contains_key(&ip(4)));
+    }
+}
@@ -1,15 +1,33 @@
-use actix_web::{web, App, HttpResponse, HttpServer, Responder, middleware};
+use actix_web::{web, App, HttpResponse, HttpServer, Resource, Responder, Route, middleware};
+use actix_web::dev::Service;
+use actix_web::http::{Method, StatusCode};
+use actix_web::ResponseError;
 use actix_cors::Cors;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use tracing_subscriber::EnvFilter;
+use crate::config::Config;
+use crate::lockout::LoginLimiter;
+use crate::metrics::{Metrics, PROMETHEUS_CONTENT_TYPE};
 use crate::models::*;
 use crate::storage::Storage;
+use crate::xlsx::{employees_to_xlsx, XLSX_CONTENT_TYPE};
 
+mod auth;
+mod config;
+mod lockout;
+mod metrics;
 mod models;
+mod proxy;
+mod reminders;
 mod storage;
+mod tls;
+mod xlsx;
 
 struct AppState {
     storage: Arc<Storage>,
-    sessions: Mutex<std::collections::HashMap<String, String>>,
+    config: Config,
+    login_limiter: LoginLimiter,
+    metrics: Metrics,
 }
 
 async fn onboard_client(data: web::Data<AppState>, req: web::Json<OnboardingRequest>) -> impl Responder {
@@ -17,336 +35,2857 @@ async fn onboard_client(data: web::Data<AppState>, req: web::Json<OnboardingRequ
         Ok(_) => {},
         Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
     }
+    let (sector, revenue) = match req.canonical_classification() {
+        Ok(classification) => classification,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    };
+
+    let password_hash = match auth::hash_password(&req.generated_password, &data.config) {
+        Ok(hash) => hash,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&e)),
+    };
 
-    let new_client = Client::new(
+    let mut new_client = Client::new(
         req.business_name.clone(),
         req.business_website.clone(),
-        req.business_sector.clone(),
-        req.revenue.clone(),
+        sector,
+        revenue,
         req.goals.clone(),
         req.email.clone(),
         req.job_title.clone(),
         req.generated_username.clone(),
-        req.generated_password.clone(),
+        password_hash,
     );
+    new_client.custom_goal_text = req.custom_goal_text.as_deref().map(sanitize_string).filter(|s| !s.is_empty());
+    new_client.other_service_text = req.other_service_text.as_deref().map(sanitize_string).filter(|s| !s.is_empty());
 
-    match data.storage.create_client(&new_client) {
-        Ok(_) => HttpResponse::Created().json(ApiResponse::success(new_client, "Client created successfully")),
-        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Database error: {}", e))),
+    if let Err(e) = data.storage.create_client(&new_client, &req.services, &req.platforms) {
+        return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Database error: {}", e)));
+    }
+    if let Err(e) = data.storage.record_password_hash(&new_client.id, &new_client.password_hash, data.config.password_history) {
+        log_event(LogLevel::Warn, &format!("Failed to record password history for {}: {}", new_client.id, e));
+    }
+
+    let session = if req.auto_login {
+        // The account already exists at this point, so a failed login only costs the client a
+        // separate sign-in rather than failing the whole onboarding.
+        match start_login_session(&data, &new_client) {
+            Ok(session) => Some(session),
+            Err(e) => {
+                log_event(LogLevel::Warn, &format!("Failed to start session for new client {}: {}", new_client.id, e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let response = OnboardingResponse {
+        client: ClientProfile::from_client(new_client, req.services.clone(), req.platforms.clone()),
+        session,
+    };
+    HttpResponse::Created().json(ApiResponse::success(response, "Client created successfully"))
+}
+
+fn start_login_session(data: &AppState, client: &Client) -> Result<LoginResponse, AppError> {
+    let now = chrono::Utc::now();
+    let family_id = uuid::Uuid::new_v4().to_string();
+    let session = new_session(data, &client.id, &family_id, now);
+    let (refresh_token, refresh) = new_refresh_token(data, &client.id, &family_id, now);
+    match data.storage.create_login_session(&session, &refresh, data.config.max_sessions_per_client)? {
+        0 => {},
+        evicted => log_event(LogLevel::Info, &format!("Evicted {} oldest session(s) for {}", evicted, client.id)),
+    }
+    if let Err(e) = data.storage.record_login(&client.id, now) {
+        log_event(LogLevel::Warn, &format!("Failed to record login for {}: {}", client.id, e));
     }
+
+    Ok(LoginResponse {
+        session_id: session.token,
+        client_name: client.business_name.clone(),
+        refresh_token,
+        refresh_expires_at: refresh.expires_at,
+    })
 }
 
-async fn login_client(data: web::Data<AppState>, req: web::Json<LoginRequest>) -> impl Responder {
+async fn login_client(data: web::Data<AppState>, http_req: actix_web::HttpRequest, req: web::Json<LoginRequest>) -> impl Responder {
     match req.validate() {
         Ok(_) => {},
         Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
     }
 
+    let client_ip = proxy::client_ip(&http_req, &data.config.trusted_proxies);
+    let ip = client_ip.map_or("unknown".to_string(), |ip| ip.to_string());
+
+    let now = chrono::Utc::now();
+    if let Some(until) = client_ip.and_then(|addr| data.login_limiter.locked_until(addr, now)) {
+        log_event(LogLevel::Warn, &format!("Login blocked for {} from {}: locked out", req.username, ip));
+        return HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", (until - now).num_seconds().max(1).to_string()))
+            .json(ApiResponse::<()>::error("Too many failed login attempts, try again later"));
+    }
+
     match data.storage.get_client_by_username(&req.username) {
         Ok(Some(client)) => {
-            if client.password_hash == req.password {
-                let session_id = uuid::Uuid::new_v4().to_string();
-                let mut sessions = data.sessions.lock().unwrap();
-                sessions.insert(session_id.clone(), client.id.clone());
-                
-                let response = LoginResponse {
-                    session_id,
-                    client_name: client.business_name,
+            if auth::verify_password(&req.password, &client.password_hash) {
+                if client.deactivated_at.is_some() {
+                    log_event(LogLevel::Warn, &format!("Login rejected for deactivated account {} from {}", req.username, ip));
+                    return HttpResponse::Forbidden().json(ApiResponse::<()>::error("Account is deactivated"));
+                }
+                if auth::needs_rehash(&client.password_hash, &data.config) {
+                    match auth::hash_password(&req.password, &data.config) {
+                        Ok(hash) => {
+                            if let Err(e) = data.storage.update_client_password(&client.id, &hash) {
+                                log_event(LogLevel::Warn, &format!("Failed to upgrade password hash for {}: {}", client.id, e));
+                            }
+                        },
+                        Err(e) => log_event(LogLevel::Warn, &e),
+                    }
+                }
+
+                let response = match start_login_session(&data, &client) {
+                    Ok(response) => response,
+                    Err(e) => return e.error_response(),
                 };
+                if let Some(addr) = client_ip {
+                    data.login_limiter.record_success(addr);
+                }
+                log_event(LogLevel::Info, &format!("Login succeeded for {} from {}", req.username, ip));
                 HttpResponse::Ok().json(ApiResponse::success(response, "Login successful"))
             } else {
+                log_event(LogLevel::Warn, &format!("Login failed for {} from {}", req.username, ip));
+                if let Some(addr) = client_ip {
+                    data.login_limiter.record_failure(addr, now);
+                }
                 HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid credentials"))
             }
         },
-        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error("User not found")),
+        Ok(None) => {
+            if let Some(addr) = client_ip {
+                data.login_limiter.record_failure(addr, now);
+            }
+            HttpResponse::NotFound().json(ApiResponse::<()>::error("User not found"))
+        },
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Database error: {}", e))),
     }
 }
 
-fn get_client_id_from_header(req: &actix_web::HttpRequest, sessions: &Mutex<std::collections::HashMap<String, String>>) -> Option<String> {
-    if let Some(auth_header) = req.headers().get("Authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = auth_str.trim_start_matches("Bearer ");
-                let sessions = sessions.lock().unwrap();
-                return sessions.get(token).cloned();
+fn new_session(data: &AppState, client_id: &str, family_id: &str, now: chrono::DateTime<chrono::Utc>) -> SessionData {
+    SessionData {
+        token: uuid::Uuid::new_v4().to_string(),
+        client_id: client_id.to_string(),
+        created_at: now,
+        expires_at: data.config.session_expiry(now, now),
+        refresh_family: Some(family_id.to_string()),
+    }
+}
+
+fn new_refresh_token(data: &AppState, client_id: &str, family_id: &str, now: chrono::DateTime<chrono::Utc>) -> (String, RefreshTokenData) {
+    let token = auth::generate_refresh_token();
+    let record = RefreshTokenData {
+        token_hash: auth::hash_refresh_token(&token),
+        family_id: family_id.to_string(),
+        client_id: client_id.to_string(),
+        created_at: now,
+        expires_at: now + chrono::Duration::seconds(data.config.refresh_ttl_secs),
+        rotated_at: None,
+        revoked: false,
+    };
+    (token, record)
+}
+
+async fn refresh_session(data: web::Data<AppState>, http_req: actix_web::HttpRequest, req: web::Json<RefreshRequest>) -> impl Responder {
+    if let Err(e) = req.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let ip = proxy::client_ip(&http_req, &data.config.trusted_proxies)
+        .map_or("unknown".to_string(), |ip| ip.to_string());
+
+    let token_hash = auth::hash_refresh_token(req.refresh_token.trim());
+    let current = match data.storage.get_refresh_token(&token_hash) {
+        Ok(Some(token)) => token,
+        Ok(None) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid refresh token")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Database error: {}", e))),
+    };
+
+    match data.storage.is_client_deactivated(&current.client_id) {
+        Ok(false) => {},
+        Ok(true) => return HttpResponse::Forbidden().json(ApiResponse::<()>::error("Account is deactivated")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Database error: {}", e))),
+    }
+
+    let now = chrono::Utc::now();
+    if current.expires_at <= now && current.rotated_at.is_none() && !current.revoked {
+        return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Refresh token expired"));
+    }
+
+    let rotated = if current.rotated_at.is_none() && !current.revoked {
+        let session = new_session(&data, &current.client_id, &current.family_id, now);
+        let (refresh_token, next) = new_refresh_token(&data, &current.client_id, &current.family_id, now);
+        match data.storage.rotate_refresh_token(&token_hash, &next, &session, data.config.max_sessions_per_client) {
+            Ok(true) => Some(RefreshResponse {
+                session_id: session.token,
+                refresh_token,
+                refresh_expires_at: next.expires_at,
+            }),
+            Ok(false) => None,
+            Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Database error: {}", e))),
+        }
+    } else {
+        None
+    };
+
+    match rotated {
+        Some(response) => HttpResponse::Ok().json(ApiResponse::success(response, "Session refreshed")),
+        None => {
+            if let Err(e) = data.storage.revoke_refresh_family(&current.family_id) {
+                return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Database error: {}", e)));
             }
+            log_event(LogLevel::Warn, &format!("Refresh token reuse detected for {} from {}; session family revoked", current.client_id, ip));
+            HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Refresh token has already been used"))
+        },
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::DbError(_) | AppError::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let message = match self {
+            AppError::DbError(msg) => format!("Error: {}", msg),
+            AppError::NotFound(msg) | AppError::InvalidInput(msg) | AppError::Forbidden(msg) => msg.clone(),
+            AppError::Unauthorized => "Invalid session".to_string(),
+            AppError::InternalError => "Internal server error".to_string(),
+        };
+        HttpResponse::build(self.status_code()).json(ApiResponse::<()>::error(&message))
+    }
+}
+
+fn bearer_token(req: &actix_web::HttpRequest) -> Option<&str> {
+    let auth_str = req.headers().get("Authorization")?.to_str().ok()?;
+    auth_str.strip_prefix("Bearer ")
+}
+
+fn get_client_id_from_header(req: &actix_web::HttpRequest, data: &AppState) -> Result<String, AppError> {
+    let token = bearer_token(req).ok_or(AppError::Unauthorized)?;
+    let session = data.storage.get_session(token)?.ok_or(AppError::Unauthorized)?;
+
+    let now = chrono::Utc::now();
+    if session.expires_at <= now {
+        let _ = data.storage.delete_session(token);
+        return Err(AppError::Unauthorized);
+    }
+
+    if data.storage.is_client_deactivated(&session.client_id)? {
+        return Err(AppError::Forbidden("Account is deactivated".to_string()));
+    }
+
+    if data.config.session_sliding {
+        let expires_at = data.config.session_expiry(session.created_at, now);
+        if expires_at > session.expires_at {
+            let _ = data.storage.extend_session(token, expires_at);
         }
     }
-    None
+
+    Ok(session.client_id)
+}
+
+fn is_admin_request(req: &actix_web::HttpRequest, data: &AppState) -> bool {
+    let expected = match &data.config.admin_token {
+        Some(token) => token,
+        None => return false,
+    };
+
+    match req.headers().get("X-Admin-Token").and_then(|h| h.to_str().ok()) {
+        Some(provided) => auth::constant_time_eq(provided, expected),
+        None => false,
+    }
+}
+
+async fn get_profile(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    let client = match data.storage.get_client_by_id(&client_id) {
+        Ok(Some(client)) => client,
+        Ok(None) => return HttpResponse::NotFound().json(ApiResponse::<()>::error("Client not found")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+
+    let services = match data.storage.get_client_services(&client_id) {
+        Ok(services) => services,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+
+    let platforms = match data.storage.get_client_platforms(&client_id) {
+        Ok(platforms) => platforms,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+
+    HttpResponse::Ok().json(ApiResponse::success(ClientProfile::from_client(client, services, platforms), "Profile retrieved"))
 }
 
-async fn get_employees(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
+async fn validate_session(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let token = match bearer_token(&req) {
+        Some(token) => token,
         None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
     };
 
-    match data.storage.get_employees(&client_id) {
-        Ok(employees) => HttpResponse::Ok().json(ApiResponse::success(employees, "Employees retrieved")),
+    match data.storage.get_session(token) {
+        Ok(Some(session)) if session.expires_at > chrono::Utc::now() => {
+            let status = SessionStatus { valid: true, expires_at: session.expires_at };
+            HttpResponse::Ok().json(ApiResponse::success(status, "Session is valid"))
+        },
+        Ok(_) => HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     }
 }
 
-async fn create_employee(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<CreateEmployeeRequest>) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+async fn change_password(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<ChangePasswordRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
     };
 
     if let Err(e) = body.validate() {
         return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
     }
 
-    let new_emp = Employee::new(
-        client_id,
-        body.name.clone(),
-        body.title.clone(),
-        body.salary,
-        body.status.clone()
-    );
+    let client = match data.storage.get_client_by_id(&client_id) {
+        Ok(Some(client)) => client,
+        Ok(None) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
 
-    match data.storage.create_employee(&new_emp) {
-        Ok(_) => HttpResponse::Created().json(ApiResponse::success(new_emp, "Employee created")),
+    if !auth::verify_password(&body.current_password, &client.password_hash) {
+        return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid credentials"));
+    }
+
+    let history = match data.storage.get_password_history(&client_id, data.config.password_history) {
+        Ok(history) => history,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+    if history.iter().any(|hash| auth::verify_password(&body.new_password, hash)) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error("Password was used recently, choose a different one"));
+    }
+
+    let password_hash = match auth::hash_password(&body.new_password, &data.config) {
+        Ok(hash) => hash,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&e)),
+    };
+    let keep = if body.logout_current { None } else { bearer_token(&req) };
+    match data.storage.change_client_password(&client_id, &password_hash, data.config.password_history, keep) {
+        Ok(revoked) => {
+            log_event(LogLevel::Info, &format!("Password changed for {}, {} session(s) revoked", client_id, revoked));
+            HttpResponse::Ok().json(ApiResponse::<()>::success((), "Password changed"))
+        },
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     }
 }
 
-async fn delete_employee(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<String>) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+async fn get_account_delete_preview(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
     };
-    let emp_id = path.into_inner();
 
-    match data.storage.delete_employee(&emp_id, &client_id) {
-        Ok(deleted) => {
-            if deleted > 0 {
-                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Employee deleted"))
-            } else {
-                HttpResponse::NotFound().json(ApiResponse::<()>::error("Employee not found"))
-            }
-        },
+    match data.storage.get_deletion_impact(&client_id) {
+        Ok(impact) => HttpResponse::Ok().json(ApiResponse::success(impact, "Account deletion preview")),
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     }
 }
 
-async fn update_employee_payment(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<String>, body: web::Json<UpdateEmployeePaymentRequest>) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+async fn delete_account(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<DeleteAccountRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
     };
-    let emp_id = path.into_inner();
 
-    match data.storage.update_employee_paid_status(&emp_id, &client_id, body.paid) {
-        Ok(updated) => {
-            if updated > 0 {
-                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Payment status updated"))
-            } else {
-                HttpResponse::NotFound().json(ApiResponse::<()>::error("Employee not found"))
-            }
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let client = match data.storage.get_client_by_id(&client_id) {
+        Ok(Some(client)) => client,
+        Ok(None) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+
+    if !auth::verify_password(&body.password, &client.password_hash) {
+        return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid credentials"));
+    }
+
+    match data.storage.delete_client_cascade(&client_id) {
+        Ok(impact) => {
+            log_event(LogLevel::Info, &format!("Account {} deleted", client_id));
+            HttpResponse::Ok().json(ApiResponse::success(impact, "Account deleted"))
         },
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     }
 }
 
-async fn get_tasks(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+async fn deactivate_account(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
     };
 
-    match data.storage.get_tasks(&client_id) {
-        Ok(tasks) => HttpResponse::Ok().json(ApiResponse::success(tasks, "Tasks retrieved")),
+    match data.storage.deactivate_client(&client_id, chrono::Utc::now()) {
+        Ok(revoked) => {
+            log_event(LogLevel::Info, &format!("Account {} deactivated, {} session(s) revoked", client_id, revoked));
+            HttpResponse::Ok().json(ApiResponse::<()>::success((), "Account deactivated"))
+        },
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     }
 }
 
-async fn create_task(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<CreateTaskRequest>) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+async fn reactivate_account(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    if !is_admin_request(&req, &data) {
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error("Admin access required"));
+    }
+
+    let client_id = path.into_inner();
+    match data.storage.reactivate_client(&client_id) {
+        Ok(updated) if updated > 0 => {
+            log_event(LogLevel::Info, &format!("Account {} reactivated", client_id));
+            HttpResponse::Ok().json(ApiResponse::<()>::success((), "Account reactivated"))
+        },
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<()>::error("Client not found")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn reset_sandbox(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    if !data.config.sandbox_mode {
+        return HttpResponse::NotFound().json(ApiResponse::<()>::error("Sandbox mode is not enabled"));
+    }
+
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
     };
 
-    let new_task = Task::new(client_id, body.title.clone(), body.priority.clone());
+    let priorities = match data.storage.get_task_priorities(&client_id) {
+        Ok(priorities) => priorities,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
 
-    match data.storage.create_task(&new_task) {
-        Ok(_) => HttpResponse::Created().json(ApiResponse::success(new_task, "Task created")),
+    let seed = SandboxSeed::demo(&client_id, &priorities, chrono::Utc::now().date_naive());
+    if let Err(e) = data.storage.reset_sandbox(&client_id, &seed) {
+        return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e)));
+    }
+
+    match data.storage.get_summary(&client_id) {
+        Ok(summary) => HttpResponse::Ok().json(ApiResponse::success(summary, "Sandbox data reset")),
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     }
 }
 
-async fn update_task_status(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<String>, body: web::Json<UpdateTaskRequest>) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+async fn whoami(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
     };
-    let task_id = path.into_inner();
 
-    match data.storage.update_task_status(&task_id, &client_id, body.done) {
-        Ok(updated) => {
-            if updated > 0 {
-                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Task updated"))
-            } else {
-                HttpResponse::NotFound().json(ApiResponse::<()>::error("Task not found"))
-            }
+    match data.storage.get_client_by_id(&client_id) {
+        Ok(Some(client)) => {
+            let identity = WhoAmI {
+                client_id: client.id,
+                username: client.username,
+                business_name: client.business_name,
+            };
+            HttpResponse::Ok().json(ApiResponse::success(identity, "Authenticated"))
         },
+        Ok(None) => HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     }
 }
 
-async fn delete_task(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<String>) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+async fn get_employees(data: web::Data<AppState>, req: actix_web::HttpRequest, query: web::Query<EmployeeQuery>, page: web::Query<PageQuery>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
     };
-    let task_id = path.into_inner();
 
-    match data.storage.delete_task(&task_id, &client_id) {
-        Ok(deleted) => {
-            if deleted > 0 {
-                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Task deleted"))
-            } else {
-                HttpResponse::NotFound().json(ApiResponse::<()>::error("Task not found"))
-            }
-        },
+    if let Err(e) = query.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let page = match data.config.page(&page) {
+        Ok(page) => page,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    };
+    match data.storage.get_employees(&client_id, &query, &page.probe()) {
+        Ok(employees) => HttpResponse::Ok().json(ApiResponse::paged(employees, page, "Employees retrieved")),
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     }
 }
 
-async fn get_events(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+async fn export_employees_xlsx(data: web::Data<AppState>, req: actix_web::HttpRequest, query: web::Query<EmployeeQuery>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = query.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let employees = match data.storage.get_employees(&client_id, &query, &Page::all()) {
+        Ok(employees) => employees,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     };
 
-    match data.storage.get_events(&client_id) {
-        Ok(events) => HttpResponse::Ok().json(ApiResponse::success(events, "Events retrieved")),
+    match employees_to_xlsx(&employees) {
+        Ok(bytes) => HttpResponse::Ok()
+            .content_type(XLSX_CONTENT_TYPE)
+            .insert_header(("Content-Disposition", "attachment; filename=\"employees.xlsx\""))
+            .body(bytes),
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     }
 }
 
-async fn create_event(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<CreateEventRequest>) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+async fn get_unassigned_employees(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
     };
 
-    if let Err(e) = body.validate() {
+    match data.storage.get_unassigned_employees(&client_id) {
+        Ok(employees) => HttpResponse::Ok().json(ApiResponse::success(employees, "Unassigned employees retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn create_employee(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<CreateEmployeeRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = body.validate(data.config.max_salary) {
         return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
     }
 
-    let new_event = Event::new(
+    let new_emp = Employee::new(
         client_id,
+        body.name.clone(),
         body.title.clone(),
-        body.description.clone().unwrap_or_default(),
-        body.start_date.clone(),
-        body.start_time.clone().unwrap_or_default(),
-        body.end_date.clone(),
-        body.end_time.clone().unwrap_or_default(),
-        body.color.clone(),
+        body.salary,
+        body.status.clone()
     );
 
-    match data.storage.create_event(&new_event) {
-        Ok(_) => HttpResponse::Created().json(ApiResponse::success(new_event, "Event created")),
+    match data.storage.create_employee(&new_emp) {
+        Ok(_) => HttpResponse::Created().json(ApiResponse::success(new_emp, "Employee created")),
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     }
 }
 
-async fn delete_event(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<String>) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+async fn import_employees(data: web::Data<AppState>, req: actix_web::HttpRequest, query: web::Query<ImportQuery>, body: String) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
     };
-    let event_id = path.into_inner();
 
-    match data.storage.delete_event(&event_id, &client_id) {
+    let (rows, failed) = match parse_employee_csv(&body, data.config.max_salary) {
+        Ok(parsed) => parsed,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    };
+
+    if query.mode == ImportMode::Strict && !failed.is_empty() {
+        let message = format!("Import aborted: {} invalid row(s)", failed.len());
+        return HttpResponse::BadRequest().json(ApiResponse::error_with_data(ImportReport { imported: 0, failed }, &message));
+    }
+
+    let employees: Vec<Employee> = rows
+        .into_iter()
+        .map(|row| Employee::new(client_id.clone(), row.name, row.title, row.salary, row.status))
+        .collect();
+
+    match data.storage.create_employees(&employees) {
+        Ok(imported) => HttpResponse::Ok().json(ApiResponse::success(ImportReport { imported, failed }, "Employees imported")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn delete_employee(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let emp_id = path.into_inner();
+
+    match data.storage.delete_employee(&emp_id, &client_id) {
         Ok(deleted) => {
             if deleted > 0 {
-                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Event deleted"))
+                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Employee deleted"))
             } else {
-                HttpResponse::NotFound().json(ApiResponse::<()>::error("Event not found"))
+                HttpResponse::NotFound().json(ApiResponse::<()>::error("Employee not found"))
             }
         },
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
     }
 }
 
-async fn get_dashboard(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
-    let client_id = match get_client_id_from_header(&req, &data.sessions) {
-        Some(id) => id,
-        None => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("Invalid session")),
+async fn clone_employee(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>, body: web::Json<CloneEmployeeRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
     };
+    let emp_id = path.into_inner();
 
-    match data.storage.get_dashboard_stats(&client_id) {
-        Ok(stats) => HttpResponse::Ok().json(ApiResponse::success(stats, "Dashboard stats retrieved")),
-        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
     }
-}
 
-async fn health_check() -> impl Responder {
-    HttpResponse::Ok().json(HealthCheckResponse {
-        status: "OK".to_string(),
-        uptime: 0, 
-        database_connected: true,
-        timestamp: chrono::Utc::now().to_rfc3339(),
-    })
-}
+    let template = match data.storage.get_employee(&emp_id, &client_id) {
+        Ok(Some(emp)) => emp,
+        Ok(None) => return HttpResponse::NotFound().json(ApiResponse::<()>::error("Employee not found")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
 
-async fn not_found() -> impl Responder {
-    HttpResponse::NotFound().json(ApiResponse::<()>::error("Route not found"))
+    let new_emp = template.clone_as(body.name.trim().to_string());
+
+    match data.storage.create_employee(&new_emp) {
+        Ok(_) => HttpResponse::Created().json(ApiResponse::success(new_emp, "Employee cloned")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    let db_path = "qads.db";
-    let storage = match Storage::new(db_path) {
-        Ok(s) => Arc::new(s),
-        Err(e) => {
-            eprintln!("Failed to initialize database: {}", e);
-            std::process::exit(1);
-        }
+async fn terminate_employee(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>, body: Option<web::Json<TerminateEmployeeRequest>>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
     };
+    let emp_id = path.into_inner();
+    let body = body.map(|b| b.into_inner()).unwrap_or_default();
 
-    let app_state = web::Data::new(AppState {
-        storage: storage.clone(),
-        sessions: Mutex::new(std::collections::HashMap::new()),
-    });
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
 
-    println!("Server running at http://127.0.0.1:8080");
+    let termination_date = body
+        .termination_date
+        .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string());
 
-    HttpServer::new(move || {
-        let cors = Cors::default()
-            .allow_any_origin()
-            .allow_any_method()
-            .allow_any_header()
-            .max_age(3600);
+    match data.storage.terminate_employee(&emp_id, &client_id, &termination_date) {
+        Ok(updated) => {
+            if updated > 0 {
+                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Employee terminated"))
+            } else {
+                HttpResponse::NotFound().json(ApiResponse::<()>::error("Employee not found"))
+            }
+        },
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+fn purge_response(result: rusqlite::Result<PurgeOutcome>, entity: &str) -> HttpResponse {
+    match result {
+        Ok(PurgeOutcome::Purged) => HttpResponse::Ok().json(ApiResponse::<()>::success((), &format!("{} permanently deleted", entity))),
+        Ok(PurgeOutcome::StillActive) => HttpResponse::Conflict().json(ApiResponse::<()>::error(&format!("{} must be deleted before it can be purged", entity))),
+        Ok(PurgeOutcome::NotFound) => HttpResponse::NotFound().json(ApiResponse::<()>::error(&format!("{} not found", entity))),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn purge_employee(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    purge_response(data.storage.purge_employee(&path.into_inner(), &client_id), "Employee")
+}
+
+async fn purge_task(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    purge_response(data.storage.purge_task(&path.into_inner(), &client_id), "Task")
+}
+
+async fn purge_event(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    purge_response(data.storage.purge_event(&path.into_inner(), &client_id), "Event")
+}
+
+async fn update_employee_payment(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>, body: web::Json<UpdateEmployeePaymentRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let emp_id = path.into_inner();
+
+    match data.storage.update_employee_paid_status(&emp_id, &client_id, body.paid) {
+        Ok(updated) => {
+            if updated > 0 {
+                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Payment status updated"))
+            } else {
+                HttpResponse::NotFound().json(ApiResponse::<()>::error("Employee not found"))
+            }
+        },
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn update_employee_salary(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>, body: web::Json<UpdateSalaryRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = body.validate(data.config.max_salary) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    match data.storage.update_employee_salary(&path.into_inner(), &client_id, body.salary) {
+        Ok(updated) if updated > 0 => HttpResponse::Ok().json(ApiResponse::<()>::success((), "Salary updated")),
+        Ok(_) => HttpResponse::NotFound().json(ApiResponse::<()>::error("Employee not found")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_employee_detail(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    match data.storage.get_employee_detail(&path.into_inner(), &client_id) {
+        Ok(Some(detail)) => HttpResponse::Ok().json(ApiResponse::success(detail, "Employee detail retrieved")),
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error("Employee not found")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_employee_payments(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let emp_id = path.into_inner();
+
+    match data.storage.employee_exists(&emp_id, &client_id) {
+        Ok(true) => {},
+        Ok(false) => return HttpResponse::NotFound().json(ApiResponse::<()>::error("Employee not found")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+
+    match data.storage.get_payment_events(&emp_id, &client_id) {
+        Ok(events) => HttpResponse::Ok().json(ApiResponse::success(events, "Payment history retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn bulk_update_employee_payment(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<BulkPaymentRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    match data.storage.bulk_update_employee_paid_status(&body.ids, &client_id, body.paid) {
+        Ok(updated) => HttpResponse::Ok().json(ApiResponse::success(BulkUpdateResponse { updated }, "Payment statuses updated")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_tasks(data: web::Data<AppState>, req: actix_web::HttpRequest, query: web::Query<TaskQuery>, page: web::Query<PageQuery>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = query.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let page = match data.config.page(&page) {
+        Ok(page) => page,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    };
+    match data.storage.get_tasks(&client_id, &query, &page.probe()) {
+        Ok(tasks) => HttpResponse::Ok().json(ApiResponse::paged(tasks, page, "Tasks retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn bulk_tag_tasks(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<BulkTagRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let mut ids = body.ids.clone();
+    ids.sort();
+    ids.dedup();
+    let tag = body.tag.trim();
+
+    match data.storage.count_owned_tasks(&ids, &client_id) {
+        Ok(count) if count == ids.len() => {},
+        Ok(_) => return HttpResponse::NotFound().json(ApiResponse::<()>::error("One or more tasks were not found")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+
+    if body.action == TagAction::Add {
+        match data.storage.count_tasks_at_tag_limit(&ids, &client_id, tag, MAX_TASK_TAGS) {
+            Ok(0) => {},
+            Ok(_) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&format!("A task cannot have more than {} tags", MAX_TASK_TAGS))),
+            Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+        }
+    }
+
+    match data.storage.bulk_tag_tasks(&ids, &client_id, tag, body.action) {
+        Ok(updated) => HttpResponse::Ok().json(ApiResponse::success(BulkUpdateResponse { updated }, "Task tags updated")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+fn resolve_task_priority(data: &AppState, client_id: &str, requested: &str) -> Result<String, AppError> {
+    let allowed = data.storage.get_task_priorities(client_id)?;
+    match_priority(&allowed, requested)
+        .ok_or_else(|| AppError::InvalidInput(format!("Priority must be one of: {}", allowed.join(", "))))
+}
+
+async fn get_task_priorities(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    match data.storage.get_task_priorities(&client_id) {
+        Ok(priorities) => HttpResponse::Ok().json(ApiResponse::success(priorities, "Task priorities retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn update_task_priorities(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<TaskPrioritiesRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let priorities: Vec<String> = body.priorities.iter().map(|p| p.trim().to_string()).collect();
+
+    match data.storage.set_task_priorities(&client_id, &priorities) {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success(priorities, "Task priorities updated")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn export_tasks_markdown(data: web::Data<AppState>, req: actix_web::HttpRequest, query: web::Query<TaskQuery>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = query.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let query = TaskQuery { sort: TaskSort::Board, ..query.into_inner() };
+    let tasks = match data.storage.get_tasks(&client_id, &query, &Page::all()) {
+        Ok(tasks) => tasks,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+
+    let priorities = match data.storage.get_task_priorities(&client_id) {
+        Ok(priorities) => priorities,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/markdown; charset=utf-8")
+        .body(tasks_to_markdown(&group_tasks_by_priority(priorities, tasks)))
+}
+
+async fn get_task_facets(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    match data.storage.get_task_facets(&client_id) {
+        Ok(facets) => HttpResponse::Ok().json(ApiResponse::success(facets, "Task facets retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_tasks_by_priority(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    let query = TaskQuery::default();
+    let tasks = match data.storage.get_tasks(&client_id, &query, &Page::all()) {
+        Ok(tasks) => tasks,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+
+    let priorities = match data.storage.get_task_priorities(&client_id) {
+        Ok(priorities) => priorities,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+
+    let columns = group_tasks_by_priority(priorities, tasks);
+    HttpResponse::Ok().json(ApiResponse::success(columns, "Tasks retrieved"))
+}
+
+async fn archive_task(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>, body: web::Json<ArchiveTaskRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let task_id = path.into_inner();
+
+    match data.storage.set_task_archived(&task_id, &client_id, body.archived) {
+        Ok(updated) => {
+            if updated > 0 {
+                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Task updated"))
+            } else {
+                HttpResponse::NotFound().json(ApiResponse::<()>::error("Task not found"))
+            }
+        },
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn assign_task(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>, body: web::Json<AssignTaskRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let task_id = path.into_inner();
+    let assignee_id = body.assignee_id.as_deref().map(str::trim);
+
+    match data.storage.set_task_assignee(&task_id, &client_id, assignee_id) {
+        Ok(AssignOutcome::Updated) => HttpResponse::Ok().json(ApiResponse::<()>::success((), "Task updated")),
+        Ok(AssignOutcome::TaskNotFound) => HttpResponse::NotFound().json(ApiResponse::<()>::error("Task not found")),
+        Ok(AssignOutcome::AssigneeNotFound) => HttpResponse::BadRequest().json(ApiResponse::<()>::error("Assignee is not an employee of this client")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn cleanup_tasks(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    match data.storage.delete_archived_completed_tasks(&client_id) {
+        Ok(deleted) => HttpResponse::Ok().json(ApiResponse::success(BulkDeleteResponse { deleted }, "Archived tasks cleaned up")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn reorder_task(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>, body: web::Json<ReorderTaskRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let task_id = path.into_inner();
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let priority = match resolve_task_priority(&data, &client_id, &body.priority) {
+        Ok(priority) => priority,
+        Err(e) => return e.error_response(),
+    };
+
+    match data.storage.reorder_task(&task_id, &client_id, &priority, body.position) {
+        Ok(updated) => {
+            if updated > 0 {
+                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Task reordered"))
+            } else {
+                HttpResponse::NotFound().json(ApiResponse::<()>::error("Task not found"))
+            }
+        },
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn create_task(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<CreateTaskRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let priority = match resolve_task_priority(&data, &client_id, &body.priority) {
+        Ok(priority) => priority,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Some(assignee_id) = &body.assignee_id {
+        match data.storage.employee_exists(assignee_id, &client_id) {
+            Ok(true) => {},
+            Ok(false) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error("Assignee is not an employee of this client")),
+            Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+        }
+    }
+
+    let mut new_task = Task::new(client_id, body.title.clone(), priority);
+    new_task.tags = normalize_tags(&body.tags);
+    new_task.assignee_id = body.assignee_id.clone();
+    new_task.position = match data.storage.next_task_position(&new_task.client_id, &new_task.priority) {
+        Ok(position) => position,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+
+    match data.storage.create_task(&new_task) {
+        Ok(_) => HttpResponse::Created().json(ApiResponse::success(new_task, "Task created")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn update_task_status(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>, body: web::Json<UpdateTaskRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let task_id = path.into_inner();
+
+    match data.storage.update_task_status(&task_id, &client_id, body.done) {
+        Ok(updated) => {
+            if updated > 0 {
+                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Task updated"))
+            } else {
+                HttpResponse::NotFound().json(ApiResponse::<()>::error("Task not found"))
+            }
+        },
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn delete_task(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let task_id = path.into_inner();
+
+    match data.storage.delete_task(&task_id, &client_id) {
+        Ok(deleted) => {
+            if deleted > 0 {
+                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Task deleted"))
+            } else {
+                HttpResponse::NotFound().json(ApiResponse::<()>::error("Task not found"))
+            }
+        },
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_events(data: web::Data<AppState>, req: actix_web::HttpRequest, page: web::Query<PageQuery>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    let page = match data.config.page(&page) {
+        Ok(page) => page,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    };
+    match data.storage.get_events(&client_id, &page.probe()) {
+        Ok(events) => HttpResponse::Ok().json(ApiResponse::paged(events, page, "Events retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn create_event(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<CreateEventRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let new_event = body.to_event(client_id);
+
+    match data.storage.create_event(&new_event) {
+        Ok(_) => HttpResponse::Created().json(ApiResponse::success(new_event, "Event created")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn create_events_batch(data: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<BatchCreateEventsRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let new_events: Vec<Event> = body.events.iter().map(|ev| ev.to_event(client_id.clone())).collect();
+
+    match data.storage.create_events(&new_events) {
+        Ok(_) => HttpResponse::Created().json(ApiResponse::success(new_events, "Events created")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn delete_event(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let event_id = path.into_inner();
+
+    match data.storage.delete_event(&event_id, &client_id) {
+        Ok(deleted) => {
+            if deleted > 0 {
+                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Event deleted"))
+            } else {
+                HttpResponse::NotFound().json(ApiResponse::<()>::error("Event not found"))
+            }
+        },
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn create_event_reminder(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>, body: web::Json<CreateReminderRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let event_id = path.into_inner();
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    let event = match data.storage.get_event(&event_id, &client_id) {
+        Ok(Some(event)) => event,
+        Ok(None) => return HttpResponse::NotFound().json(ApiResponse::<()>::error("Event not found")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+
+    let starts_at = match event.starts_at(data.config.event_timezone) {
+        Some(starts_at) => starts_at,
+        None => return HttpResponse::UnprocessableEntity().json(ApiResponse::<()>::error("Event has no valid start date and time")),
+    };
+    if starts_at <= chrono::Utc::now() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error("Event has already started"));
+    }
+
+    let reminder = EventReminder::new(&event, starts_at, body.minutes_before, body.channel());
+    match data.storage.create_event_reminder(&reminder) {
+        Ok(_) => HttpResponse::Created().json(ApiResponse::success(reminder, "Reminder scheduled")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn add_event_attendees(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>, body: web::Json<AddAttendeesRequest>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let event_id = path.into_inner();
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+
+    match data.storage.add_event_attendees(&event_id, &client_id, &body.employee_ids) {
+        Ok(AttendeeOutcome::Added(added)) => HttpResponse::Ok().json(ApiResponse::success(AttendeesAddedResponse { added }, "Attendees added")),
+        Ok(AttendeeOutcome::EventNotFound) => HttpResponse::NotFound().json(ApiResponse::<()>::error("Event not found")),
+        Ok(AttendeeOutcome::EmployeeNotFound(employee_id)) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&format!("Employee {} not found", employee_id))),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn remove_event_attendee(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<(EntityId, EntityId)>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let (event_id, employee_id) = path.into_inner();
+
+    match data.storage.remove_event_attendee(&event_id, &employee_id, &client_id) {
+        Ok(removed) => {
+            if removed > 0 {
+                HttpResponse::Ok().json(ApiResponse::<()>::success((), "Attendee removed"))
+            } else {
+                HttpResponse::NotFound().json(ApiResponse::<()>::error("Attendee not found"))
+            }
+        },
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_employee_events(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let emp_id = path.into_inner();
+
+    match data.storage.employee_exists(&emp_id, &client_id) {
+        Ok(true) => {},
+        Ok(false) => return HttpResponse::NotFound().json(ApiResponse::<()>::error("Employee not found")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+
+    match data.storage.get_employee_events(&emp_id, &client_id) {
+        Ok(events) => HttpResponse::Ok().json(ApiResponse::success(events, "Employee events retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn upload_document(data: web::Data<AppState>, req: actix_web::HttpRequest, query: web::Query<UploadDocumentQuery>, body: web::Bytes) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    if let Err(e) = query.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+    if body.is_empty() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error("Document content cannot be empty"));
+    }
+
+    if let Some(task_id) = &query.task_id {
+        match data.storage.task_exists(task_id, &client_id) {
+            Ok(true) => {},
+            Ok(false) => return HttpResponse::NotFound().json(ApiResponse::<()>::error("Task not found")),
+            Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+        }
+    }
+    if let Some(event_id) = &query.event_id {
+        match data.storage.event_exists(event_id, &client_id) {
+            Ok(true) => {},
+            Ok(false) => return HttpResponse::NotFound().json(ApiResponse::<()>::error("Event not found")),
+            Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+        }
+    }
+
+    let file_type = req
+        .headers()
+        .get(actix_web::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or(DEFAULT_DOCUMENT_TYPE)
+        .to_string();
+
+    let document = Document::new(client_id, &query, file_type, body.len());
+    match data.storage.create_document(&document, &body) {
+        Ok(_) => HttpResponse::Created().json(ApiResponse::success(document, "Document uploaded")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_documents(data: web::Data<AppState>, req: actix_web::HttpRequest, page: web::Query<PageQuery>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    let page = match data.config.page(&page) {
+        Ok(page) => page,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    };
+    match data.storage.get_documents(&client_id, &page.probe()) {
+        Ok(documents) => HttpResponse::Ok().json(ApiResponse::paged(documents, page, "Documents retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn download_document(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    match data.storage.get_document_content(&path.into_inner(), &client_id) {
+        // The stored type is whatever the uploader claimed, so it's never echoed back: a
+        // "text/html" upload must download as bytes, not render inline.
+        Ok(Some((document, content))) => HttpResponse::Ok()
+            .content_type(DEFAULT_DOCUMENT_TYPE)
+            .insert_header(("X-Content-Type-Options", "nosniff"))
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", document.name.replace('"', ""))))
+            .body(content),
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error("Document not found")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_task_documents(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let task_id = path.into_inner();
+
+    match data.storage.task_exists(&task_id, &client_id) {
+        Ok(true) => {},
+        Ok(false) => return HttpResponse::NotFound().json(ApiResponse::<()>::error("Task not found")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+
+    match data.storage.get_task_documents(&task_id, &client_id) {
+        Ok(documents) => HttpResponse::Ok().json(ApiResponse::success(documents, "Task documents retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_event_documents(data: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<EntityId>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+    let event_id = path.into_inner();
+
+    match data.storage.event_exists(&event_id, &client_id) {
+        Ok(true) => {},
+        Ok(false) => return HttpResponse::NotFound().json(ApiResponse::<()>::error("Event not found")),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+
+    match data.storage.get_event_documents(&event_id, &client_id) {
+        Ok(documents) => HttpResponse::Ok().json(ApiResponse::success(documents, "Event documents retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_payroll_breakdown(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    match data.storage.get_payroll_breakdown(&client_id) {
+        Ok(breakdown) => HttpResponse::Ok().json(ApiResponse::success(breakdown, "Payroll breakdown retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_activity(data: web::Data<AppState>, req: actix_web::HttpRequest, page: web::Query<PageQuery>) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    let page = match data.config.page(&page) {
+        Ok(page) => page,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    };
+    match data.storage.get_activity(&client_id, &page.probe()) {
+        Ok(items) => HttpResponse::Ok().json(ApiResponse::paged(items, page, "Activity retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_dashboard(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    match data.storage.get_dashboard_stats(&client_id) {
+        Ok(stats) => HttpResponse::Ok().json(ApiResponse::success(stats, "Dashboard stats retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_dashboard_trends(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    match data.storage.get_dashboard_trends(&client_id, chrono::Utc::now(), chrono::Duration::days(7)) {
+        Ok(trends) => HttpResponse::Ok().json(ApiResponse::success(trends, "Dashboard trends retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn get_summary(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    let client_id = match get_client_id_from_header(&req, &data) {
+        Ok(id) => id,
+        Err(e) => return e.error_response(),
+    };
+
+    match data.storage.get_summary(&client_id) {
+        Ok(summary) => HttpResponse::Ok().json(ApiResponse::success(summary, "Summary retrieved")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn vacuum_database(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    if !is_admin_request(&req, &data) {
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error("Admin access required"));
+    }
+
+    match data.storage.vacuum() {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report, "Database vacuumed")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn check_database_integrity(data: web::Data<AppState>, req: actix_web::HttpRequest) -> impl Responder {
+    if !is_admin_request(&req, &data) {
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error("Admin access required"));
+    }
+
+    match data.storage.integrity_check() {
+        Ok(messages) => HttpResponse::Ok().json(ApiResponse::success(IntegrityReport::from_messages(messages), "Integrity check completed")),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn health_check() -> impl Responder {
+    HttpResponse::Ok().json(HealthCheckResponse {
+        status: "OK".to_string(),
+        uptime: 0, 
+        database_connected: true,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+async fn prometheus_metrics(data: web::Data<AppState>) -> impl Responder {
+    let active_sessions = match data.storage.count_active_sessions(chrono::Utc::now()) {
+        Ok(count) => count,
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    };
+
+    match data.metrics.render(active_sessions) {
+        Ok(body) => HttpResponse::Ok().content_type(PROMETHEUS_CONTENT_TYPE).body(body),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Error: {}", e))),
+    }
+}
+
+async fn not_found() -> impl Responder {
+    HttpResponse::NotFound().json(ApiResponse::<()>::error("Route not found"))
+}
+
+fn method_not_allowed(allow: String) -> HttpResponse {
+    HttpResponse::MethodNotAllowed()
+        .insert_header(("Allow", allow))
+        .json(ApiResponse::<()>::error("Method not allowed"))
+}
+
+// Routes sharing a path live on one resource so an unsupported method gets a 405 listing the
+// supported ones, instead of falling through to the 404 default service.
+fn resource(path: &str, routes: Vec<(Method, Route)>) -> Resource {
+    let allow = routes.iter().map(|(method, _)| method.as_str()).collect::<Vec<_>>().join(", ");
+    let resource = routes
+        .into_iter()
+        .fold(web::resource(path), |resource, (method, route)| resource.route(route.method(method)));
+    resource.default_service(web::to(move || {
+        let allow = allow.clone();
+        async move { method_not_allowed(allow) }
+    }))
+}
+
+fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::PathConfig::default().error_handler(|err, _req| {
+        actix_web::error::InternalError::from_response(
+            err,
+            HttpResponse::BadRequest().json(ApiResponse::<()>::error(INVALID_ID_MESSAGE)),
+        )
+        .into()
+    }))
+    .service(resource("/health", vec![(Method::GET, web::to(health_check))]))
+    .service(resource("/metrics/prometheus", vec![(Method::GET, web::to(prometheus_metrics))]))
+    .service(resource("/onboarding", vec![(Method::POST, web::to(onboard_client))]))
+    .service(resource("/login", vec![(Method::POST, web::to(login_client))]))
+    .service(
+        web::scope("/api")
+            .service(resource("/login/refresh", vec![(Method::POST, web::to(refresh_session))]))
+            .service(resource("/session/validate", vec![(Method::GET, web::to(validate_session))]))
+            .service(resource("/whoami", vec![(Method::GET, web::to(whoami))]))
+            .service(resource("/password", vec![(Method::PUT, web::to(change_password))]))
+            .service(resource("/account", vec![(Method::DELETE, web::to(delete_account))]))
+            .service(resource("/account/delete-preview", vec![(Method::GET, web::to(get_account_delete_preview))]))
+            .service(resource("/account/deactivate", vec![(Method::POST, web::to(deactivate_account))]))
+            .service(resource("/sandbox/reset", vec![(Method::POST, web::to(reset_sandbox))]))
+            .service(resource("/profile", vec![(Method::GET, web::to(get_profile))]))
+            .service(resource("/dashboard", vec![(Method::GET, web::to(get_dashboard))]))
+            .service(resource("/dashboard/trends", vec![(Method::GET, web::to(get_dashboard_trends))]))
+            .service(resource("/summary", vec![(Method::GET, web::to(get_summary))]))
+            .service(resource("/payroll/breakdown", vec![(Method::GET, web::to(get_payroll_breakdown))]))
+            .service(resource("/activity", vec![(Method::GET, web::to(get_activity))]))
+            .service(resource("/settings/task-priorities", vec![
+                (Method::GET, web::to(get_task_priorities)),
+                (Method::PUT, web::to(update_task_priorities)),
+            ]))
+            .service(resource("/admin/vacuum", vec![(Method::POST, web::to(vacuum_database))]))
+            .service(resource("/admin/integrity", vec![(Method::GET, web::to(check_database_integrity))]))
+            .service(resource("/admin/clients/{id}/reactivate", vec![(Method::POST, web::to(reactivate_account))]))
+            .service(resource("/employees", vec![(Method::GET, web::to(get_employees)), (Method::POST, web::to(create_employee))]))
+            .service(resource("/employees/bulk-payment", vec![(Method::POST, web::to(bulk_update_employee_payment))]))
+            .service(resource("/employees/import", vec![(Method::POST, web::to(import_employees))]))
+            .service(resource("/employees/unassigned", vec![(Method::GET, web::to(get_unassigned_employees))]))
+            .service(resource("/employees/export.xlsx", vec![(Method::GET, web::to(export_employees_xlsx))]))
+            .service(resource("/employees/{id}", vec![(Method::DELETE, web::to(delete_employee))]))
+            .service(resource("/employees/{id}/purge", vec![(Method::DELETE, web::to(purge_employee))]))
+            .service(resource("/employees/{id}/payment", vec![(Method::PUT, web::to(update_employee_payment))]))
+            .service(resource("/employees/{id}/payments", vec![(Method::GET, web::to(get_employee_payments))]))
+            .service(resource("/employees/{id}/salary", vec![(Method::PUT, web::to(update_employee_salary))]))
+            .service(resource("/employees/{id}/detail", vec![(Method::GET, web::to(get_employee_detail))]))
+            .service(resource("/employees/{id}/events", vec![(Method::GET, web::to(get_employee_events))]))
+            .service(resource("/employees/{id}/terminate", vec![(Method::POST, web::to(terminate_employee))]))
+            .service(resource("/employees/{id}/clone", vec![(Method::POST, web::to(clone_employee))]))
+            .service(resource("/tasks", vec![(Method::GET, web::to(get_tasks)), (Method::POST, web::to(create_task))]))
+            .service(resource("/tasks/by-priority", vec![(Method::GET, web::to(get_tasks_by_priority))]))
+            .service(resource("/tasks/facets", vec![(Method::GET, web::to(get_task_facets))]))
+            .service(resource("/tasks/bulk-tag", vec![(Method::POST, web::to(bulk_tag_tasks))]))
+            .service(resource("/tasks/export.md", vec![(Method::GET, web::to(export_tasks_markdown))]))
+            .service(resource("/tasks/cleanup", vec![(Method::DELETE, web::to(cleanup_tasks))]))
+            .service(resource("/tasks/{id}", vec![(Method::PUT, web::to(update_task_status)), (Method::DELETE, web::to(delete_task))]))
+            .service(resource("/tasks/{id}/purge", vec![(Method::DELETE, web::to(purge_task))]))
+            .service(resource("/tasks/{id}/reorder", vec![(Method::PUT, web::to(reorder_task))]))
+            .service(resource("/tasks/{id}/archive", vec![(Method::PUT, web::to(archive_task))]))
+            .service(resource("/tasks/{id}/assignee", vec![(Method::PUT, web::to(assign_task))]))
+            .service(resource("/tasks/{id}/documents", vec![(Method::GET, web::to(get_task_documents))]))
+            .service(resource("/events", vec![(Method::GET, web::to(get_events)), (Method::POST, web::to(create_event))]))
+            .service(resource("/events/batch", vec![(Method::POST, web::to(create_events_batch))]))
+            .service(resource("/events/{id}", vec![(Method::DELETE, web::to(delete_event))]))
+            .service(resource("/events/{id}/purge", vec![(Method::DELETE, web::to(purge_event))]))
+            .service(resource("/events/{id}/attendees", vec![(Method::POST, web::to(add_event_attendees))]))
+            .service(resource("/events/{id}/reminder", vec![(Method::POST, web::to(create_event_reminder))]))
+            .service(resource("/events/{id}/documents", vec![(Method::GET, web::to(get_event_documents))]))
+            .service(
+                resource("/documents", vec![(Method::GET, web::to(get_documents)), (Method::POST, web::to(upload_document))])
+                    .app_data(web::PayloadConfig::new(MAX_DOCUMENT_BYTES))
+            )
+            .service(resource("/documents/{id}", vec![(Method::GET, web::to(download_document))]))
+            .service(resource("/events/{id}/attendees/{employee_id}", vec![(Method::DELETE, web::to(remove_event_attendee))]))
+    )
+    .default_service(web::route().to(not_found));
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let config = match Config::from_env() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let db_path = "qads.db";
+    let storage = match Storage::new(db_path) {
+        Ok(s) if config.db_read_replica => s.with_read_replica(),
+        other => other,
+    };
+    let storage = match storage {
+        Ok(s) => Arc::new(s),
+        Err(e) => {
+            eprintln!("Failed to initialize database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(&config.log_filter))
+        .init();
+
+    if let Err(e) = reminders::spawn_scheduler(storage.clone(), std::time::Duration::from_secs(config.reminder_poll_secs), config.event_timezone) {
+        eprintln!("Failed to start reminder scheduler: {}", e);
+        std::process::exit(1);
+    }
+
+    let tls_config = match config.tls_paths().map(|(cert, key)| tls::load_server_config(cert, key)) {
+        Some(Ok(tls_config)) => Some(tls_config),
+        Some(Err(e)) => {
+            eprintln!("Invalid TLS configuration: {}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+
+    let workers = config.workers;
+    let keep_alive_secs = config.keep_alive_secs;
+
+    let metrics = match Metrics::new() {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("Failed to register metrics: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let login_limiter = LoginLimiter::new(config.login_max_failures, config.login_lockout_secs, config.login_allowlist.clone());
+    let app_state = web::Data::new(AppState {
+        storage: storage.clone(),
+        config,
+        login_limiter,
+        metrics,
+    });
+
+    let mut server = HttpServer::new(move || {
+        let cors = Cors::default()
+            .allow_any_origin()
+            .allow_any_method()
+            .allow_any_header()
+            .max_age(3600);
+        let state = app_state.clone();
+
+        // The last wrap is the outermost, so metrics also count responses that CORS or the
+        // logger produce or rewrite.
+        App::new()
+            .wrap(cors)
+            .wrap(middleware::Logger::default())
+            .wrap_fn(move |req, srv| {
+                let fut = srv.call(req);
+                let state = state.clone();
+                async move {
+                    let res = fut.await;
+                    let status = match &res {
+                        Ok(res) => res.status(),
+                        Err(e) => e.as_response_error().status_code(),
+                    };
+                    state.metrics.observe_response(status.as_u16());
+                    res
+                }
+            })
+            .app_data(app_state.clone())
+            .configure(routes)
+    });
+
+    if let Some(n) = workers {
+        server = server.workers(n);
+    }
+    if let Some(secs) = keep_alive_secs {
+        server = server.keep_alive(std::time::Duration::from_secs(secs));
+    }
+
+    log_event(LogLevel::Info, &format!(
+        "Workers: {}, keep-alive: {}",
+        workers.map_or("default".to_string(), |n| n.to_string()),
+        keep_alive_secs.map_or("default".to_string(), |s| format!("{}s", s)),
+    ));
+    match tls_config {
+        Some(tls_config) => {
+            println!("Server running at https://127.0.0.1:8080");
+            server.bind_rustls_0_23(("127.0.0.1", 8080), tls_config)?.run().await
+        }
+        None => {
+            println!("Server running at http://127.0.0.1:8080");
+            server.bind(("127.0.0.1", 8080))?.run().await
+        }
+    }
+}
 
-        App::new()
-            .wrap(cors)
-            .wrap(middleware::Logger::default())
-            .app_data(app_state.clone())
-            .route("/health", web::get().to(health_check))
-            .route("/onboarding", web::post().to(onboard_client))
-            .route("/login", web::post().to(login_client))
-            .service(
-                web::scope("/api")
-                    .route("/dashboard", web::get().to(get_dashboard))
-                    .route("/employees", web::get().to(get_employees))
-                    .route("/employees", web::post().to(create_employee))
-                    .route("/employees/{id}", web::delete().to(delete_employee))
-                    .route("/employees/{id}/payment", web::put().to(update_employee_payment))
-                    .route("/tasks", web::get().to(get_tasks))
-                    .route("/tasks", web::post().to(create_task))
-                    .route("/tasks/{id}", web::put().to(update_task_status))
-                    .route("/tasks/{id}", web::delete().to(delete_task))
-                    .route("/events", web::get().to(get_events))
-                    .route("/events", web::post().to(create_event))
-                    .route("/events/{id}", web::delete().to(delete_event))
-            )
-            .default_service(web::route().to(not_found))
-    })
-    .bind(("127.0.0.1", 8080))?
-    .run()
-    .await
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::MessageBody;
+    use actix_web::dev::ServiceResponse;
+    use actix_web::test;
+    use chrono::{Duration, Utc};
+    use serde_json::{json, Value};
+
+    fn test_config() -> Config {
+        Config {
+            argon2_memory_kib: 64,
+            argon2_iterations: 1,
+            ..Config::default()
+        }
+    }
+
+    fn test_state(config: Config) -> web::Data<AppState> {
+        web::Data::new(AppState {
+            storage: Arc::new(Storage::new(":memory:").unwrap()),
+            login_limiter: LoginLimiter::new(config.login_max_failures, config.login_lockout_secs, config.login_allowlist.clone()),
+            metrics: Metrics::new().unwrap(),
+            config,
+        })
+    }
+
+    macro_rules! test_app {
+        ($state:expr) => {
+            test::init_service(App::new().app_data($state.clone()).configure(routes)).await
+        };
+    }
+
+    fn seed_client(state: &AppState, username: &str) -> Client {
+        let client = Client::new(
+            "Acme".to_string(),
+            "https://acme.test".to_string(),
+            "Retail".to_string(),
+            "100k".to_string(),
+            "Grow".to_string(),
+            format!("{}@acme.test", username),
+            "Owner".to_string(),
+            username.to_string(),
+            auth::hash_password(PASSWORD, &state.config).unwrap(),
+        );
+        state.storage.create_client(&client, &[], &[]).unwrap();
+        client
+    }
+
+    fn seed_session(state: &AppState, client_id: &str, age: Duration, remaining: Duration) -> String {
+        let now = Utc::now();
+        let family_id = uuid::Uuid::new_v4().to_string();
+        let mut session = new_session(state, client_id, &family_id, now - age);
+        session.expires_at = now + remaining;
+        let (_, refresh) = new_refresh_token(state, client_id, &family_id, now - age);
+        state.storage.create_login_session(&session, &refresh, state.config.max_sessions_per_client).unwrap();
+        session.token
+    }
+
+    fn authed(state: &AppState, username: &str) -> (Client, String) {
+        let client = seed_client(state, username);
+        let token = seed_session(state, &client.id, Duration::seconds(0), Duration::hours(1));
+        (client, token)
+    }
+
+    fn seed_employee(state: &AppState, client_id: &str, name: &str, salary: f64, status: &str) -> Employee {
+        let employee = Employee::new(client_id.to_string(), name.to_string(), "Clerk".to_string(), salary, status.to_string());
+        state.storage.create_employee(&employee).unwrap();
+        employee
+    }
+
+    const PASSWORD: &str = "correct horse battery";
+
+    fn onboarding_body(username: &str) -> Value {
+        json!({
+            "business_name": "Acme",
+            "business_website": "https://acme.test",
+            "business_sector": "Retail",
+            "revenue": "Pre-revenue",
+            "goals": "Grow",
+            "custom_goal_text": null,
+            "email": format!("{}@acme.test", username),
+            "job_title": "Owner",
+            "services": ["Payroll", "Scheduling"],
+            "other_service_text": null,
+            "platforms": ["Web", "iOS"],
+            "generated_username": username,
+            "generated_password": PASSWORD,
+        })
+    }
+
+    fn login_request(username: &str, password: &str) -> test::TestRequest {
+        test::TestRequest::post().uri("/login").set_json(json!({ "username": username, "password": password }))
+    }
+
+    fn bearer(token: &str) -> (&'static str, String) {
+        ("Authorization", format!("Bearer {}", token))
+    }
+
+    async fn send<S, R, B>(app: &S, req: R) -> (StatusCode, Value)
+    where
+        S: Service<R, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody,
+    {
+        let res = test::call_service(app, req).await;
+        let status = res.status();
+        let body = test::read_body(res).await;
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[actix_web::test]
+    async fn unsupported_method_on_known_route_returns_405_with_allow() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+
+        let req = test::TestRequest::get().uri(&format!("/api/tasks/{}", uuid::Uuid::new_v4())).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers().get("Allow").unwrap(), "PUT, DELETE");
+
+        let (status, body) = send(&app, test::TestRequest::post().uri("/api/summary").to_request()).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(body["message"], "Method not allowed");
+    }
+
+    #[actix_web::test]
+    async fn unknown_path_still_returns_404() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+
+        for uri in ["/nope", "/api/nope"] {
+            let (status, body) = send(&app, test::TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            assert_eq!(body["message"], "Route not found");
+        }
+    }
+
+    #[actix_web::test]
+    async fn sliding_session_is_extended_but_capped_at_max_lifetime() {
+        let state = test_state(Config {
+            session_sliding: true,
+            session_ttl_secs: 600,
+            session_max_lifetime_secs: 1200,
+            ..test_config()
+        });
+        let app = test_app!(state);
+        let client = seed_client(&state, "slider");
+
+        let fresh = seed_session(&state, &client.id, Duration::seconds(0), Duration::seconds(60));
+        let (status, _) = send(&app, test::TestRequest::get().uri("/api/whoami").insert_header(bearer(&fresh)).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        let extended = state.storage.get_session(&fresh).unwrap().unwrap();
+        assert!(extended.expires_at > Utc::now() + Duration::seconds(500));
+
+        let old = seed_session(&state, &client.id, Duration::seconds(1100), Duration::seconds(60));
+        let (status, _) = send(&app, test::TestRequest::get().uri("/api/whoami").insert_header(bearer(&old)).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        let capped = state.storage.get_session(&old).unwrap().unwrap();
+        assert_eq!(capped.expires_at, capped.created_at + Duration::seconds(1200));
+    }
+
+    #[actix_web::test]
+    async fn session_past_max_lifetime_is_rejected() {
+        let state = test_state(Config { session_sliding: true, ..test_config() });
+        let app = test_app!(state);
+        let client = seed_client(&state, "expired");
+
+        let token = seed_session(&state, &client.id, Duration::days(31), Duration::seconds(-1));
+        let (status, body) = send(&app, test::TestRequest::get().uri("/api/whoami").insert_header(bearer(&token)).to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["message"], "Invalid session");
+        assert!(state.storage.get_session(&token).unwrap().is_none());
+    }
+
+    #[actix_web::test]
+    async fn session_lookup_failure_is_a_server_error() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let client = seed_client(&state, "broken");
+        let token = seed_session(&state, &client.id, Duration::seconds(0), Duration::seconds(60));
+
+        state.storage.execute_raw("DROP TABLE sessions").unwrap();
+        let (status, _) = send(&app, test::TestRequest::get().uri("/api/whoami").insert_header(bearer(&token)).to_request()).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[actix_web::test]
+    async fn onboarding_persists_services_and_platforms_for_profile() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+
+        let (status, body) = send(&app, test::TestRequest::post().uri("/onboarding").set_json(onboarding_body("acme")).to_request()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["data"]["services"], json!(["Payroll", "Scheduling"]));
+
+        let (status, body) = send(&app, login_request("acme", PASSWORD).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        let token = body["data"]["session_id"].as_str().unwrap().to_string();
+
+        let (status, body) = send(&app, test::TestRequest::get().uri("/api/profile").insert_header(bearer(&token)).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["username"], "acme");
+        assert_eq!(body["data"]["services"], json!(["Payroll", "Scheduling"]));
+        assert_eq!(body["data"]["platforms"], json!(["Web", "iOS"]));
+        assert!(body["data"].get("password_hash").is_none());
+    }
+
+    #[actix_web::test]
+    async fn profile_requires_a_session() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+
+        let (status, _) = send(&app, test::TestRequest::get().uri("/api/profile").to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn bulk_payment_only_touches_owned_employees() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (owner, token) = authed(&state, "owner");
+        let other = seed_client(&state, "other");
+        let mine = seed_employee(&state, &owner.id, "Ada", 3000.0, "Active");
+        let theirs = seed_employee(&state, &other.id, "Bob", 3000.0, "Active");
+
+        let req = test::TestRequest::post()
+            .uri("/api/employees/bulk-payment")
+            .insert_header(bearer(&token))
+            .set_json(json!({ "ids": [mine.id, theirs.id], "paid": true }))
+            .to_request();
+        let (status, body) = send(&app, req).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["updated"], 1);
+        assert!(state.storage.get_employee(&mine.id, &owner.id).unwrap().unwrap().paid);
+        assert!(!state.storage.get_employee(&theirs.id, &other.id).unwrap().unwrap().paid);
+    }
+
+    #[actix_web::test]
+    async fn bulk_payment_rejects_more_than_max_ids() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (_, token) = authed(&state, "owner");
+
+        let ids: Vec<String> = (0..=MAX_BULK_IDS).map(|_| uuid::Uuid::new_v4().to_string()).collect();
+        let req = test::TestRequest::post()
+            .uri("/api/employees/bulk-payment")
+            .insert_header(bearer(&token))
+            .set_json(json!({ "ids": ids, "paid": true }))
+            .to_request();
+        let (status, body) = send(&app, req).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], format!("Cannot update more than {} employees at once", MAX_BULK_IDS));
+    }
+
+    #[actix_web::test]
+    async fn legacy_password_is_rehashed_on_login() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let mut client = seed_client(&state, "legacy");
+        state.storage.update_client_password(&client.id, PASSWORD).unwrap();
+
+        let (status, _) = send(&app, login_request("legacy", "wrong password").to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        client = state.storage.get_client_by_id(&client.id).unwrap().unwrap();
+        assert_eq!(client.password_hash, PASSWORD);
+
+        let (status, _) = send(&app, login_request("legacy", PASSWORD).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        client = state.storage.get_client_by_id(&client.id).unwrap().unwrap();
+        assert!(client.password_hash.starts_with("$argon2id$"));
+        assert!(auth::verify_password(PASSWORD, &client.password_hash));
+    }
+
+    #[actix_web::test]
+    async fn vacuum_requires_the_admin_token() {
+        let state = test_state(Config { admin_token: Some("s3cret-admin".to_string()), ..test_config() });
+        let app = test_app!(state);
+
+        for token in [None, Some("s3cret-admi"), Some("s3cret-admin2")] {
+            let mut req = test::TestRequest::post().uri("/api/admin/vacuum");
+            if let Some(token) = token {
+                req = req.insert_header(("X-Admin-Token", token));
+            }
+            let (status, body) = send(&app, req.to_request()).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            assert_eq!(body["message"], "Admin access required");
+        }
+
+        let req = test::TestRequest::post().uri("/api/admin/vacuum").insert_header(("X-Admin-Token", "s3cret-admin")).to_request();
+        let (status, body) = send(&app, req).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["data"]["reclaimed_bytes"].is_u64());
+    }
+
+    #[actix_web::test]
+    async fn vacuum_is_disabled_without_an_admin_token() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+
+        let req = test::TestRequest::post().uri("/api/admin/vacuum").insert_header(("X-Admin-Token", "")).to_request();
+        let (status, _) = send(&app, req).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    const IMPORT_CSV: &str = "name,title,salary,status\nAda,Engineer,5000,Active\n,Clerk,100,Active\nBob,Designer,lots,Active\nCarol,Manager,7000,Active\n";
+
+    fn import_request(token: &str, mode: Option<&str>) -> test::TestRequest {
+        let uri = match mode {
+            Some(mode) => format!("/api/employees/import?mode={}", mode),
+            None => "/api/employees/import".to_string(),
+        };
+        test::TestRequest::post().uri(&uri).insert_header(bearer(token)).set_payload(IMPORT_CSV)
+    }
+
+    #[actix_web::test]
+    async fn strict_import_rejects_the_whole_file() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+
+        let (status, body) = send(&app, import_request(&token, None).to_request()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Import aborted: 2 invalid row(s)");
+        assert_eq!(body["data"]["imported"], 0);
+        assert_eq!(body["data"]["failed"][0]["line"], 3);
+        assert_eq!(body["data"]["failed"][1]["line"], 4);
+        assert_eq!(state.storage.count_employees(&client.id).unwrap(), 0);
+    }
+
+    #[actix_web::test]
+    async fn report_import_keeps_valid_rows_and_lists_failures() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+
+        let (status, body) = send(&app, import_request(&token, Some("report")).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["imported"], 2);
+        assert_eq!(body["data"]["failed"][0]["error"], "Employee name is required");
+        assert_eq!(body["data"]["failed"].as_array().unwrap().len(), 2);
+        assert_eq!(state.storage.count_employees(&client.id).unwrap(), 2);
+    }
+
+    fn event_body(title: &str, start_date: &str) -> Value {
+        json!({
+            "title": title,
+            "start_date": start_date,
+            "start_time": "09:00",
+            "end_date": start_date,
+            "end_time": "10:00",
+            "color": "#336699",
+        })
+    }
+
+    fn batch_request(token: &str, events: Vec<Value>) -> test::TestRequest {
+        test::TestRequest::post().uri("/api/events/batch").insert_header(bearer(token)).set_json(json!({ "events": events }))
+    }
+
+    #[actix_web::test]
+    async fn clean_event_batch_is_created() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+
+        let events = vec![event_body("Kickoff", "2030-01-01"), event_body("Review", "2030-01-08")];
+        let (status, body) = send(&app, batch_request(&token, events).to_request()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["data"].as_array().unwrap().len(), 2);
+        assert_eq!(state.storage.count_events(&client.id).unwrap(), 2);
+    }
+
+    #[actix_web::test]
+    async fn event_batch_with_an_invalid_event_creates_nothing() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+
+        let events = vec![event_body("Kickoff", "2030-01-01"), event_body("Review", "01/08/2030")];
+        let (status, body) = send(&app, batch_request(&token, events).to_request()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Event 2: Start date must be in YYYY-MM-DD format");
+        assert_eq!(state.storage.count_events(&client.id).unwrap(), 0);
+    }
+
+    #[actix_web::test]
+    async fn event_batch_size_is_capped() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+
+        let events = (0..=MAX_EVENT_BATCH).map(|i| event_body(&format!("Event {}", i), "2030-01-01")).collect();
+        let (status, body) = send(&app, batch_request(&token, events).to_request()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], format!("Cannot create more than {} events at once", MAX_EVENT_BATCH));
+        assert_eq!(state.storage.count_events(&client.id).unwrap(), 0);
+    }
+
+    #[actix_web::test]
+    async fn login_updates_last_login_at() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let client = seed_client(&state, "owner");
+        assert!(client.last_login_at.is_none());
+
+        let (status, _) = send(&app, login_request("owner", "wrong password").to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(state.storage.get_client_by_id(&client.id).unwrap().unwrap().last_login_at.is_none());
+
+        let before = Utc::now();
+        let (status, body) = send(&app, login_request("owner", PASSWORD).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        let last_login_at = state.storage.get_client_by_id(&client.id).unwrap().unwrap().last_login_at.unwrap();
+        assert!(last_login_at >= before - Duration::seconds(1));
+
+        let token = body["data"]["session_id"].as_str().unwrap().to_string();
+        let (_, body) = send(&app, test::TestRequest::get().uri("/api/profile").insert_header(bearer(&token)).to_request()).await;
+        assert!(body["data"]["last_login_at"].is_string());
+    }
+
+    #[actix_web::test]
+    async fn cloning_a_terminated_employee_yields_an_active_one() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        let leaver = seed_employee(&state, &client.id, "Ada", 5000.0, "Active");
+        state.storage.terminate_employee(&leaver.id, &client.id, "2030-01-31").unwrap();
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/employees/{}/clone", leaver.id))
+            .insert_header(bearer(&token))
+            .set_json(json!({ "name": "Grace" }))
+            .to_request();
+        let (status, body) = send(&app, req).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["data"]["status"], ACTIVE_STATUS);
+        assert_eq!(body["data"]["termination_date"], Value::Null);
+        assert_eq!(state.storage.get_dashboard_stats(&client.id).unwrap().total_employees, 1);
+    }
+
+    #[actix_web::test]
+    async fn cloning_another_clients_employee_is_not_found() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        let other = seed_client(&state, "other");
+        let foreign = seed_employee(&state, &other.id, "Ada", 5000.0, "Active");
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/employees/{}/clone", foreign.id))
+            .insert_header(bearer(&token))
+            .set_json(json!({ "name": "Grace" }))
+            .to_request();
+        let (status, _) = send(&app, req).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(state.storage.get_dashboard_stats(&client.id).unwrap().total_employees, 0);
+        assert_eq!(state.storage.get_dashboard_stats(&other.id).unwrap().total_employees, 1);
+    }
+
+    #[actix_web::test]
+    async fn configured_max_salary_applies_to_create_update_and_import() {
+        let state = test_state(Config { max_salary: 10_000.0, ..test_config() });
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+
+        let req = test::TestRequest::post()
+            .uri("/api/employees")
+            .insert_header(bearer(&token))
+            .set_json(json!({ "name": "Ada", "title": "CEO", "salary": 20_000.0, "status": "Active" }))
+            .to_request();
+        let (status, body) = send(&app, req).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Salary cannot exceed $10000.00");
+
+        let employee = seed_employee(&state, &client.id, "Bob", 5000.0, "Active");
+        let req = test::TestRequest::put()
+            .uri(&format!("/api/employees/{}/salary", employee.id))
+            .insert_header(bearer(&token))
+            .set_json(json!({ "salary": 10_001.0 }))
+            .to_request();
+        let (status, _) = send(&app, req).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post()
+            .uri("/api/employees/import?mode=report")
+            .insert_header(bearer(&token))
+            .set_payload("name,title,salary,status\nCarol,CTO,9000,Active\nDan,CFO,12000,Active\n")
+            .to_request();
+        let (status, body) = send(&app, req).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["imported"], 1);
+        assert_eq!(body["data"]["failed"][0]["error"], "Salary cannot exceed $10000.00");
+    }
+
+    #[actix_web::test]
+    async fn integrity_check_reports_a_healthy_database() {
+        let state = test_state(Config { admin_token: Some("s3cret-admin".to_string()), ..test_config() });
+        let app = test_app!(state);
+
+        let (status, _) = send(&app, test::TestRequest::get().uri("/api/admin/integrity").to_request()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::get().uri("/api/admin/integrity").insert_header(("X-Admin-Token", "s3cret-admin")).to_request();
+        let (status, body) = send(&app, req).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], json!({ "ok": true, "problems": [] }));
+    }
+
+    #[actix_web::test]
+    async fn employees_filter_by_salary_range() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        for (name, salary) in [("Ada", 1000.0), ("Bob", 2000.0), ("Carol", 3000.0)] {
+            seed_employee(&state, &client.id, name, salary, "Active");
+        }
+
+        let get = |uri: &str| test::TestRequest::get().uri(uri).insert_header(bearer(&token)).to_request();
+        let names = |body: &Value| -> Vec<String> {
+            let mut names: Vec<String> = body["data"].as_array().unwrap().iter().map(|e| e["name"].as_str().unwrap().to_string()).collect();
+            names.sort();
+            names
+        };
+
+        let (status, body) = send(&app, get("/api/employees?min_salary=1500&max_salary=3000")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(names(&body), ["Bob", "Carol"]);
+
+        let (_, body) = send(&app, get("/api/employees?max_salary=1000")).await;
+        assert_eq!(names(&body), ["Ada"]);
+
+        let (status, body) = send(&app, get("/api/employees?min_salary=3000&max_salary=1000")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "min_salary cannot be greater than max_salary");
+    }
+
+    fn refresh_request(refresh_token: &str) -> test::TestRequest {
+        test::TestRequest::post().uri("/api/login/refresh").set_json(json!({ "refresh_token": refresh_token }))
+    }
+
+    fn whoami_request(token: &str) -> test::TestRequest {
+        test::TestRequest::get().uri("/api/whoami").insert_header(bearer(token))
+    }
+
+    #[actix_web::test]
+    async fn login_stores_session_and_refresh_token_together() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let client = seed_client(&state, "owner");
+
+        let (_, body) = send(&app, login_request("owner", PASSWORD).to_request()).await;
+        let session = state.storage.get_session(body["data"]["session_id"].as_str().unwrap()).unwrap().unwrap();
+        let refresh_hash = auth::hash_refresh_token(body["data"]["refresh_token"].as_str().unwrap());
+        let refresh = state.storage.get_refresh_token(&refresh_hash).unwrap().unwrap();
+        assert_eq!(session.refresh_family.as_deref(), Some(refresh.family_id.as_str()));
+        assert_eq!(state.storage.count_sessions(&client.id).unwrap(), 1);
+    }
+
+    #[actix_web::test]
+    async fn refresh_retires_the_previous_session_of_the_family() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let client = seed_client(&state, "owner");
+
+        let (_, login) = send(&app, login_request("owner", PASSWORD).to_request()).await;
+        let old_session = login["data"]["session_id"].as_str().unwrap();
+        let old_refresh = login["data"]["refresh_token"].as_str().unwrap();
+
+        let (status, refreshed) = send(&app, refresh_request(old_refresh).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        let new_session = refreshed["data"]["session_id"].as_str().unwrap();
+
+        let (status, _) = send(&app, whoami_request(old_session).to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&app, whoami_request(new_session).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(state.storage.count_sessions(&client.id).unwrap(), 1);
+
+        let (status, body) = send(&app, refresh_request(old_refresh).to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["message"], "Refresh token has already been used");
+        let (status, _) = send(&app, whoami_request(new_session).to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn expired_refresh_token_is_rejected_without_a_new_session() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let client = seed_client(&state, "owner");
+
+        let issued = Utc::now() - Duration::seconds(state.config.refresh_ttl_secs + 60);
+        let family_id = uuid::Uuid::new_v4().to_string();
+        let session = new_session(&state, &client.id, &family_id, issued);
+        let (token, refresh) = new_refresh_token(&state, &client.id, &family_id, issued);
+        state.storage.create_login_session(&session, &refresh, state.config.max_sessions_per_client).unwrap();
+
+        let (status, body) = send(&app, refresh_request(&token).to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["message"], "Refresh token expired");
+        assert_eq!(state.storage.count_sessions(&client.id).unwrap(), 1);
+        let stored = state.storage.get_refresh_token(&refresh.token_hash).unwrap().unwrap();
+        assert!(stored.rotated_at.is_none());
+    }
+
+    #[actix_web::test]
+    async fn unassigned_employees_exclude_assignees_and_other_clients() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        let busy = seed_employee(&state, &client.id, "Busy", 1000.0, "Active");
+        seed_employee(&state, &client.id, "Idle", 1000.0, "Active");
+        let other = seed_client(&state, "other");
+        seed_employee(&state, &other.id, "Elsewhere", 1000.0, "Active");
+        let mut task = Task::new(client.id.clone(), "Ship".to_string(), "High".to_string());
+        task.assignee_id = Some(busy.id.clone());
+        state.storage.create_task(&task).unwrap();
+
+        let list = test::TestRequest::get().uri("/api/employees/unassigned").insert_header(bearer(&token));
+        let (status, body) = send(&app, list.to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        let names: Vec<&str> = body["data"].as_array().unwrap().iter().map(|e| e["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["Idle"]);
+    }
+
+    #[actix_web::test]
+    async fn task_assignee_can_be_changed_and_cleared() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        let other = seed_client(&state, "other");
+        let ada = seed_employee(&state, &client.id, "Ada", 1000.0, "Active");
+        let stranger = seed_employee(&state, &other.id, "Eve", 1000.0, "Active");
+        let task = Task::new(client.id.clone(), "Ship".to_string(), "High".to_string());
+        state.storage.create_task(&task).unwrap();
+
+        let assign = |task_id: &str, assignee: Value| {
+            test::TestRequest::put()
+                .uri(&format!("/api/tasks/{}/assignee", task_id))
+                .insert_header(bearer(&token))
+                .set_json(json!({ "assignee_id": assignee }))
+                .to_request()
+        };
+        let assignee = || state.storage.get_tasks(&client.id, &TaskQuery::default(), &Page::all()).unwrap()[0].assignee_id.clone();
+
+        let (status, _) = send(&app, assign(&task.id, json!(ada.id))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(assignee(), Some(ada.id.clone()));
+
+        let (status, body) = send(&app, assign(&task.id, json!(stranger.id))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Assignee is not an employee of this client");
+        assert_eq!(assignee(), Some(ada.id.clone()));
+
+        let (status, _) = send(&app, assign(&task.id, Value::Null)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(assignee(), None);
+
+        let (status, _) = send(&app, assign(&uuid::Uuid::new_v4().to_string(), json!(ada.id))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn configured_priorities_gate_task_creation() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (_, token) = authed(&state, "owner");
+
+        let levels = json!(["Critical", "High", "Medium", "Low"]);
+        let configure = test::TestRequest::put()
+            .uri("/api/settings/task-priorities")
+            .insert_header(bearer(&token))
+            .set_json(json!({ "priorities": levels }));
+        let (status, body) = send(&app, configure.to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], levels);
+
+        let create = |priority: &str| {
+            test::TestRequest::post()
+                .uri("/api/tasks")
+                .insert_header(bearer(&token))
+                .set_json(json!({ "title": "Fix outage", "priority": priority }))
+                .to_request()
+        };
+        let (status, body) = send(&app, create("Critical")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["data"]["priority"], "Critical");
+
+        let (status, body) = send(&app, create("Blocker")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Priority must be one of: Critical, High, Medium, Low");
+    }
+
+    #[actix_web::test]
+    async fn reordering_moves_a_task_ahead_in_the_list() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        let mut first = Task::new(client.id.clone(), "First".to_string(), "High".to_string());
+        first.position = 0.0;
+        let mut second = Task::new(client.id.clone(), "Second".to_string(), "High".to_string());
+        second.position = 1.0;
+        state.storage.create_task(&first).unwrap();
+        state.storage.create_task(&second).unwrap();
+
+        let list = || test::TestRequest::get().uri("/api/tasks").insert_header(bearer(&token)).to_request();
+        let titles = |body: &Value| -> Vec<String> {
+            body["data"].as_array().unwrap().iter().map(|t| t["title"].as_str().unwrap().to_string()).collect()
+        };
+        let (_, body) = send(&app, list()).await;
+        assert_eq!(titles(&body), ["First", "Second"]);
+
+        let reorder = test::TestRequest::put()
+            .uri(&format!("/api/tasks/{}/reorder", second.id))
+            .insert_header(bearer(&token))
+            .set_json(json!({ "priority": "High", "position": -1.0 }));
+        let (status, _) = send(&app, reorder.to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (_, body) = send(&app, list()).await;
+        assert_eq!(titles(&body), ["Second", "First"]);
+    }
+
+    #[actix_web::test]
+    async fn bulk_tag_adds_and_removes_tags() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        let mut first = Task::new(client.id.clone(), "First".to_string(), "High".to_string());
+        first.tags = vec!["urgent".to_string()];
+        let second = Task::new(client.id.clone(), "Second".to_string(), "High".to_string());
+        state.storage.create_task(&first).unwrap();
+        state.storage.create_task(&second).unwrap();
+
+        let bulk = |ids: Vec<&str>, tag: &str, action: &str| {
+            test::TestRequest::post()
+                .uri("/api/tasks/bulk-tag")
+                .insert_header(bearer(&token))
+                .set_json(json!({ "ids": ids, "tag": tag, "action": action }))
+                .to_request()
+        };
+        let tagged = |tag: &str| {
+            let query = TaskQuery { tag: Some(tag.to_string()), ..TaskQuery::default() };
+            let mut titles: Vec<String> = state.storage.get_tasks(&client.id, &query, &Page::all()).unwrap().into_iter().map(|t| t.title).collect();
+            titles.sort();
+            titles
+        };
+
+        let (status, body) = send(&app, bulk(vec![&first.id, &second.id], "urgent", "add")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["updated"], 1);
+        assert_eq!(tagged("urgent"), ["First", "Second"]);
+
+        let (status, body) = send(&app, bulk(vec![&first.id, &second.id, &first.id], "urgent", "remove")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["updated"], 2);
+        assert!(tagged("urgent").is_empty());
+
+        let (status, _) = send(&app, bulk(vec![&first.id, &uuid::Uuid::new_v4().to_string()], "urgent", "add")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(tagged("urgent").is_empty());
+    }
+
+    #[actix_web::test]
+    async fn purge_requires_a_soft_deleted_row() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        let task = Task::new(client.id.clone(), "Ship".to_string(), "High".to_string());
+        state.storage.create_task(&task).unwrap();
+
+        let purge = || {
+            test::TestRequest::delete()
+                .uri(&format!("/api/tasks/{}/purge", task.id))
+                .insert_header(bearer(&token))
+                .to_request()
+        };
+
+        let (status, _) = send(&app, purge()).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(state.storage.get_tasks(&client.id, &TaskQuery::default(), &Page::all()).unwrap().len(), 1);
+
+        state.storage.delete_task(&task.id, &client.id).unwrap();
+        let (status, _) = send(&app, purge()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = send(&app, purge()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn whoami_returns_the_session_identity() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+
+        let (status, body) = send(&app, whoami_request(&token).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["client_id"], client.id);
+        assert_eq!(body["data"]["username"], "owner");
+        assert_eq!(body["data"]["business_name"], client.business_name);
+
+        let (status, body) = send(&app, whoami_request("not-a-session").to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["message"], "Invalid session");
+        let (status, _) = send(&app, test::TestRequest::get().uri("/api/whoami").to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn documents_download_as_opaque_attachments() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (_, token) = authed(&state, "owner");
+
+        let upload = test::TestRequest::post()
+            .uri("/api/documents?name=page.html")
+            .insert_header(bearer(&token))
+            .insert_header(("Content-Type", "text/html"))
+            .set_payload("<script>alert(1)</script>");
+        let (status, body) = send(&app, upload.to_request()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["data"]["file_type"], "text/html");
+
+        let download = test::TestRequest::get()
+            .uri(&format!("/api/documents/{}", body["data"]["id"].as_str().unwrap()))
+            .insert_header(bearer(&token));
+        let res = test::call_service(&app, download.to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let header = |name: &str| res.headers().get(name).unwrap().to_str().unwrap().to_string();
+        assert_eq!(header("Content-Type"), DEFAULT_DOCUMENT_TYPE);
+        assert_eq!(header("X-Content-Type-Options"), "nosniff");
+        assert_eq!(header("Content-Disposition"), "attachment; filename=\"page.html\"");
+        assert_eq!(test::read_body(res).await, "<script>alert(1)</script>");
+    }
+
+    #[actix_web::test]
+    async fn task_attachments_are_listed_and_purged_with_the_task() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        let task = Task::new(client.id.clone(), "Ship".to_string(), "High".to_string());
+        state.storage.create_task(&task).unwrap();
+
+        let upload = |name: &str| {
+            test::TestRequest::post()
+                .uri(&format!("/api/documents?name={}&task_id={}", name, task.id))
+                .insert_header(bearer(&token))
+                .insert_header(("Content-Type", "text/plain"))
+                .set_payload("hello")
+                .to_request()
+        };
+        let (status, body) = send(&app, upload("notes.txt")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["data"]["task_id"], task.id);
+
+        let (status, body) = send(&app, upload("bad%0Aname.txt")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Document name cannot contain control characters");
+
+        let list = test::TestRequest::get().uri(&format!("/api/tasks/{}/documents", task.id)).insert_header(bearer(&token));
+        let (status, body) = send(&app, list.to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        assert_eq!(body["data"][0]["name"], "notes.txt");
+        assert!(body["data"][0].get("content").is_none());
+
+        for uri in [format!("/api/tasks/{}", task.id), format!("/api/tasks/{}/purge", task.id)] {
+            let (status, _) = send(&app, test::TestRequest::delete().uri(&uri).insert_header(bearer(&token)).to_request()).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        assert_eq!(state.storage.count_documents(&client.id).unwrap(), 0);
+    }
+
+    #[actix_web::test]
+    async fn task_list_is_paged_with_metadata() {
+        let state = test_state(Config { default_page_size: 2, ..test_config() });
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        for title in ["One", "Two", "Three"] {
+            state.storage.create_task(&Task::new(client.id.clone(), title.to_string(), "High".to_string())).unwrap();
+        }
+
+        let list = |uri: &str| test::TestRequest::get().uri(uri).insert_header(bearer(&token)).to_request();
+        let (status, body) = send(&app, list("/api/tasks")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"].as_array().unwrap().len(), 2);
+        assert_eq!(body["page"], json!({ "limit": 2, "offset": 0, "has_more": true }));
+
+        let (_, body) = send(&app, list("/api/tasks?offset=2")).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        assert_eq!(body["page"]["has_more"], false);
+
+        let (status, body) = send(&app, list("/api/tasks?offset=10001")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Offset cannot exceed 10000");
+    }
+
+    #[actix_web::test]
+    async fn auto_login_session_opens_the_profile() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let mut onboarding = onboarding_body("acme");
+        onboarding["auto_login"] = json!(true);
+
+        let (status, body) = send(&app, test::TestRequest::post().uri("/onboarding").set_json(&onboarding).to_request()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let token = body["data"]["session"]["session_id"].as_str().unwrap().to_string();
+
+        let profile = test::TestRequest::get().uri("/api/profile").insert_header(bearer(&token));
+        let (status, body) = send(&app, profile.to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["username"], "acme");
+    }
+
+    #[actix_web::test]
+    async fn onboarding_succeeds_without_a_session_when_auto_login_fails() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        state.storage.execute_raw("DROP TABLE refresh_tokens").unwrap();
+        let mut onboarding = onboarding_body("acme");
+        onboarding["auto_login"] = json!(true);
+
+        let (status, body) = send(&app, test::TestRequest::post().uri("/onboarding").set_json(&onboarding).to_request()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(body["data"]["session"].is_null());
+        assert!(state.storage.get_client_by_username("acme").unwrap().is_some());
+    }
+
+    #[actix_web::test]
+    async fn session_validation_does_not_slide_expiry() {
+        let state = test_state(Config { session_sliding: true, session_ttl_secs: 600, ..test_config() });
+        let app = test_app!(state);
+        let client = seed_client(&state, "owner");
+        let validate = |token: &str| test::TestRequest::get().uri("/api/session/validate").insert_header(bearer(token)).to_request();
+
+        let token = seed_session(&state, &client.id, Duration::seconds(0), Duration::seconds(60));
+        let before = state.storage.get_session(&token).unwrap().unwrap().expires_at;
+        let (status, body) = send(&app, validate(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["valid"], true);
+        assert_eq!(state.storage.get_session(&token).unwrap().unwrap().expires_at, before);
+
+        let expired = seed_session(&state, &client.id, Duration::seconds(120), Duration::seconds(-1));
+        let (status, body) = send(&app, validate(&expired)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["message"], "Invalid session");
+    }
+
+    #[actix_web::test]
+    async fn password_change_signs_out_other_sessions() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, current) = authed(&state, "owner");
+        let other = seed_session(&state, &client.id, Duration::seconds(0), Duration::hours(1));
+
+        let change = test::TestRequest::put()
+            .uri("/api/password")
+            .insert_header(bearer(&current))
+            .set_json(json!({ "current_password": PASSWORD, "new_password": "a different passphrase 42" }));
+        let (status, _) = send(&app, change.to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = send(&app, whoami_request(&other).to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&app, whoami_request(&current).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn deactivated_account_cannot_log_in_until_reactivated() {
+        let state = test_state(Config { admin_token: Some("s3cret-admin".to_string()), ..test_config() });
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+
+        let deactivate = test::TestRequest::post().uri("/api/account/deactivate").insert_header(bearer(&token));
+        let (status, _) = send(&app, deactivate.to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(&app, whoami_request(&token).to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, body) = send(&app, login_request("owner", PASSWORD).to_request()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["message"], "Account is deactivated");
+
+        let reactivate = test::TestRequest::post()
+            .uri(&format!("/api/admin/clients/{}/reactivate", client.id))
+            .insert_header(("X-Admin-Token", "s3cret-admin"));
+        let (status, _) = send(&app, reactivate.to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = send(&app, login_request("owner", PASSWORD).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["data"]["session_id"].is_string());
+    }
+
+    #[actix_web::test]
+    async fn account_deletion_requires_the_password_and_matches_the_preview() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        seed_employee(&state, &client.id, "Ada", 1000.0, "Active");
+
+        let preview = test::TestRequest::get().uri("/api/account/delete-preview").insert_header(bearer(&token));
+        let (status, preview) = send(&app, preview.to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let delete = |password: &str| {
+            test::TestRequest::delete()
+                .uri("/api/account")
+                .insert_header(bearer(&token))
+                .set_json(json!({ "password": password }))
+                .to_request()
+        };
+        let (status, _) = send(&app, delete("not the password")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(state.storage.get_client_by_id(&client.id).unwrap().is_some());
+
+        let (status, body) = send(&app, delete(PASSWORD)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], preview["data"]);
+        assert!(state.storage.get_client_by_id(&client.id).unwrap().is_none());
+        let (status, _) = send(&app, whoami_request(&token).to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn deactivated_account_sessions_are_forbidden() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        state.storage.deactivate_client(&client.id, chrono::Utc::now()).unwrap();
+
+        // A session written after the revocation, e.g. by a login racing the deactivation.
+        let late = seed_session(&state, &client.id, Duration::seconds(0), Duration::hours(1));
+        let (status, _) = send(&app, whoami_request(&token).to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = send(&app, whoami_request(&late).to_request()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["message"], "Account is deactivated");
+    }
+
+    #[actix_web::test]
+    async fn deactivated_account_cannot_refresh() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let client = seed_client(&state, "owner");
+        let (_, login) = send(&app, login_request("owner", PASSWORD).to_request()).await;
+        let refresh = login["data"]["refresh_token"].as_str().unwrap();
+
+        state.storage.deactivate_client(&client.id, chrono::Utc::now()).unwrap();
+        let (status, body) = send(&app, refresh_request(refresh).to_request()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["message"], "Account is deactivated");
+        assert_eq!(state.storage.count_sessions(&client.id).unwrap(), 0);
+    }
+
+    #[actix_web::test]
+    async fn recent_passwords_cannot_be_reused() {
+        let state = test_state(Config { password_history: 2, ..test_config() });
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        state.storage.record_password_hash(&client.id, &client.password_hash, 2).unwrap();
+
+        let change = |current: &str, new: &str| {
+            test::TestRequest::put()
+                .uri("/api/password")
+                .insert_header(bearer(&token))
+                .set_json(json!({ "current_password": current, "new_password": new }))
+                .to_request()
+        };
+        let second = "second passphrase 22";
+        let third = "third passphrase 333";
+
+        let (status, _) = send(&app, change(PASSWORD, second)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = send(&app, change(second, PASSWORD)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Password was used recently, choose a different one");
+
+        let (status, _) = send(&app, change(second, third)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(&app, change(third, PASSWORD)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn path_ids_must_be_uuids() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (_, token) = authed(&state, "owner");
+        let get = |uri: String| test::TestRequest::get().uri(&uri).insert_header(bearer(&token)).to_request();
+
+        let (status, body) = send(&app, get("/api/employees/not-a-uuid/detail".to_string())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], INVALID_ID_MESSAGE);
+
+        let (status, _) = send(&app, get(format!("/api/employees/{}/detail", uuid::Uuid::new_v4()))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn sandbox_reset_restores_the_demo_baseline_every_time() {
+        let state = test_state(Config { sandbox_mode: true, ..test_config() });
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        seed_employee(&state, &client.id, "Ada", 1000.0, "Active");
+        let task = Task::new(client.id.clone(), "Mine".to_string(), "High".to_string());
+        state.storage.create_task(&task).unwrap();
+        let query = UploadDocumentQuery { name: "notes.txt".to_string(), task_id: Some(task.id.clone()), event_id: None };
+        state.storage.create_document(&Document::new(client.id.clone(), &query, "text/plain".to_string(), 5), b"hello").unwrap();
+
+        let reset = || test::TestRequest::post().uri("/api/sandbox/reset").insert_header(bearer(&token)).to_request();
+        let baseline = json!({ "employees": 3, "tasks": 4, "events": 2, "documents": 1 });
+        for _ in 0..2 {
+            let (status, body) = send(&app, reset()).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            assert_eq!(body["data"], baseline);
+        }
+
+        let tasks = state.storage.get_tasks(&client.id, &TaskQuery::default(), &Page::all()).unwrap();
+        assert!(tasks.iter().all(|t| t.done == t.completed_at.is_some()));
+        assert!(state.storage.get_documents(&client.id, &Page::all()).unwrap()[0].task_id.is_none());
+    }
+
+    #[actix_web::test]
+    async fn tasks_filter_by_creation_window() {
+        let state = test_state(test_config());
+        let app = test_app!(state);
+        let (client, token) = authed(&state, "owner");
+        let fresh = Task::new(client.id.clone(), "Fresh".to_string(), "High".to_string());
+        let mut stale = Task::new(client.id.clone(), "Stale".to_string(), "High".to_string());
+        stale.created_at = Utc::now() - Duration::days(3);
+        state.storage.create_task(&fresh).unwrap();
+        state.storage.create_task(&stale).unwrap();
+
+        let list = |window: &str| {
+            test::TestRequest::get()
+                .uri(&format!("/api/tasks?created_within={}", window))
+                .insert_header(bearer(&token))
+                .to_request()
+        };
+        let titles = |body: &Value| body["data"].as_array().unwrap().iter().map(|t| t["title"].as_str().unwrap().to_string()).collect::<Vec<_>>();
+
+        let (status, body) = send(&app, list("24h")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(titles(&body), ["Fresh"]);
+
+        let (_, body) = send(&app, list("1w")).await;
+        assert_eq!(titles(&body), ["Fresh", "Stale"]);
+
+        let (status, body) = send(&app, list("3x")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["message"].as_str().unwrap().starts_with("Invalid window"));
+    }
+
+    #[actix_web::test]
+    async fn session_cap_evicts_the_least_recently_active_family() {
+        let state = test_state(Config { max_sessions_per_client: 2, ..test_config() });
+        let app = test_app!(state);
+        seed_client(&state, "owner");
+
+        let login = || async {
+            let (status, body) = send(&app, login_request("owner", PASSWORD).to_request()).await;
+            assert_eq!(status, StatusCode::OK);
+            (body["data"]["session_id"].as_str().unwrap().to_string(), body["data"]["refresh_token"].as_str().unwrap().to_string())
+        };
+        let (_, first_refresh) = login().await;
+        let (second_session, second_refresh) = login().await;
+
+        // Refreshing makes the first login the most recently active family.
+        let (status, body) = send(&app, refresh_request(&first_refresh).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        let first_session = body["data"]["session_id"].as_str().unwrap().to_string();
+
+        let (third_session, _) = login().await;
+        for (token, expected) in [(&first_session, StatusCode::OK), (&third_session, StatusCode::OK), (&second_session, StatusCode::UNAUTHORIZED)] {
+            let (status, _) = send(&app, whoami_request(token).to_request()).await;
+            assert_eq!(status, expected);
+        }
+        let (status, _) = send(&app, refresh_request(&second_refresh).to_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
 }
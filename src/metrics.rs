@@ -0,0 +1,82 @@
+use prometheus::{Encoder, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+use std::time::Instant;
+
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+pub struct Metrics {
+    registry: Registry,
+    total_requests: IntCounter,
+    requests_by_status: IntCounterVec,
+    active_sessions: IntGauge,
+    uptime_seconds: IntGauge,
+    started_at: Instant,
+}
+
+impl Metrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new();
+        let total_requests = IntCounter::new("total_requests", "Total number of HTTP requests served")?;
+        let requests_by_status = IntCounterVec::new(
+            Opts::new("requests_by_status", "HTTP requests served, by response status code"),
+            &["status"],
+        )?;
+        let active_sessions = IntGauge::new("active_sessions", "Number of unexpired sessions")?;
+        let uptime_seconds = IntGauge::new("uptime_seconds", "Seconds since the server started")?;
+
+        registry.register(Box::new(total_requests.clone()))?;
+        registry.register(Box::new(requests_by_status.clone()))?;
+        registry.register(Box::new(active_sessions.clone()))?;
+        registry.register(Box::new(uptime_seconds.clone()))?;
+
+        Ok(Self {
+            registry,
+            total_requests,
+            requests_by_status,
+            active_sessions,
+            uptime_seconds,
+            started_at: Instant::now(),
+        })
+    }
+
+    pub fn observe_response(&self, status: u16) {
+        self.total_requests.inc();
+        self.requests_by_status.with_label_values(&[&status.to_string()]).inc();
+    }
+
+    pub fn render(&self, active_sessions: i64) -> prometheus::Result<String> {
+        self.active_sessions.set(active_sessions);
+        self.uptime_seconds.set(self.started_at.elapsed().as_secs() as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_prometheus_text_exposition() {
+        let metrics = Metrics::new().unwrap();
+        metrics.observe_response(200);
+        metrics.observe_response(200);
+        metrics.observe_response(404);
+
+        let body = metrics.render(3).unwrap();
+        for line in [
+            "# HELP total_requests Total number of HTTP requests served",
+            "# TYPE total_requests counter",
+            "total_requests 3",
+            "# TYPE requests_by_status counter",
+            "requests_by_status{status=\"200\"} 2",
+            "requests_by_status{status=\"404\"} 1",
+            "# TYPE active_sessions gauge",
+            "active_sessions 3",
+            "# TYPE uptime_seconds gauge",
+        ] {
+            assert!(body.lines().any(|l| l == line), "missing {:?} in:\n{}", line, body);
+        }
+    }
+}
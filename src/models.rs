@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
-use std::collections::HashMap;
 use uuid::Uuid;
-use chrono::{DateTime, Utc, NaiveDate};
+use chrono::{DateTime, Duration, Utc, NaiveDate, NaiveTime, TimeZone};
+use chrono_tz::Tz;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Client {
@@ -16,10 +16,15 @@ pub struct Client {
     pub job_title: String,
     pub username: String,
     pub password_hash: String,
+    pub custom_goal_text: Option<String>,
+    pub other_service_text: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub deactivated_at: Option<DateTime<Utc>>,
 }
 
 impl Client {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         business_name: String,
         business_website: String,
@@ -42,7 +47,65 @@ impl Client {
             job_title,
             username,
             password_hash,
+            custom_goal_text: None,
+            other_service_text: None,
             created_at: Utc::now(),
+            last_login_at: None,
+            deactivated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionStatus {
+    pub valid: bool,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WhoAmI {
+    pub client_id: String,
+    pub username: String,
+    pub business_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClientProfile {
+    pub id: String,
+    pub business_name: String,
+    pub business_website: String,
+    pub business_sector: String,
+    pub revenue: String,
+    pub goals: String,
+    pub custom_goal_text: Option<String>,
+    pub email: String,
+    pub job_title: String,
+    pub username: String,
+    pub services: Vec<String>,
+    pub other_service_text: Option<String>,
+    pub platforms: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+impl ClientProfile {
+    pub fn from_client(client: Client, services: Vec<String>, platforms: Vec<String>) -> Self {
+        Self {
+            id: client.id,
+            business_name: client.business_name,
+            business_website: client.business_website,
+            business_sector: client.business_sector,
+            revenue: client.revenue,
+            goals: client.goals,
+            custom_goal_text: client.custom_goal_text,
+            email: client.email,
+            job_title: client.job_title,
+            username: client.username,
+            services,
+            other_service_text: client.other_service_text,
+            platforms,
+            created_at: client.created_at,
+            last_login_at: client.last_login_at,
         }
     }
 }
@@ -56,9 +119,14 @@ pub struct Employee {
     pub salary: f64,
     pub status: String,
     pub paid: bool,
+    pub termination_date: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
+pub const TERMINATED_STATUS: &str = "Terminated";
+pub const ACTIVE_STATUS: &str = "Active";
+
 impl Employee {
     pub fn new(client_id: String, name: String, title: String, salary: f64, status: String) -> Self {
         Self {
@@ -69,9 +137,22 @@ impl Employee {
             salary,
             status,
             paid: false,
+            termination_date: None,
             created_at: Utc::now(),
+            updated_at: None,
         }
     }
+
+    /// A new hire modelled on this employee. Cloning someone who has left starts the copy
+    /// as active rather than carrying over the termination.
+    pub fn clone_as(&self, name: String) -> Self {
+        let status = if self.status.eq_ignore_ascii_case(TERMINATED_STATUS) {
+            ACTIVE_STATUS.to_string()
+        } else {
+            self.status.clone()
+        };
+        Employee::new(self.client_id.clone(), name, self.title.clone(), self.salary, status)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,8 +161,74 @@ pub struct Task {
     pub client_id: String,
     pub title: String,
     pub priority: String,
+    pub position: f64,
+    pub tags: Vec<String>,
+    pub assignee_id: Option<String>,
     pub done: bool,
+    pub archived: bool,
     pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskColumn {
+    pub priority: String,
+    pub tasks: Vec<Task>,
+}
+
+pub fn group_tasks_by_priority(priorities: Vec<String>, tasks: Vec<Task>) -> Vec<TaskColumn> {
+    let mut columns: Vec<TaskColumn> = priorities
+        .into_iter()
+        .map(|priority| TaskColumn { priority, tasks: Vec::new() })
+        .collect();
+    for task in tasks {
+        match columns.iter_mut().find(|c| c.priority == task.priority) {
+            Some(column) => column.tasks.push(task),
+            None => columns.push(TaskColumn { priority: task.priority.clone(), tasks: vec![task] }),
+        }
+    }
+    columns
+}
+
+/// Escapes the characters that start inline markup. Block markers such as `#`, `-` or `1.` are
+/// left alone because the text never begins a line in the export.
+pub fn escape_markdown(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '~' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Like `escape_markdown`, but also protects a trailing run of `#` that an ATX heading would
+/// otherwise swallow as its closing sequence.
+fn escape_markdown_heading(input: &str) -> String {
+    let escaped = escape_markdown(input.trim());
+    let body = escaped.trim_end_matches('#');
+    if body.len() == escaped.len() || !(body.is_empty() || body.ends_with(' ')) {
+        return escaped;
+    }
+    format!("{}\\{}", body, &escaped[body.len()..])
+}
+
+pub fn tasks_to_markdown(columns: &[TaskColumn]) -> String {
+    let mut out = String::from("# Tasks\n");
+    for column in columns.iter().filter(|c| !c.tasks.is_empty()) {
+        out.push_str(&format!("\n## {}\n\n", escape_markdown_heading(&column.priority)));
+        for task in &column.tasks {
+            let checkbox = if task.done { "[x]" } else { "[ ]" };
+            out.push_str(&format!("- {} {}\n", checkbox, escape_markdown(&task.title)));
+        }
+    }
+    out
 }
 
 impl Task {
@@ -91,8 +238,14 @@ impl Task {
             client_id,
             title,
             priority,
+            position: 0.0,
+            tags: Vec::new(),
+            assignee_id: None,
             done: false,
+            archived: false,
             created_at: Utc::now(),
+            completed_at: None,
+            updated_at: None,
         }
     }
 }
@@ -108,10 +261,19 @@ pub struct Event {
     pub end_date: String,
     pub end_time: String,
     pub color: String,
+    pub attendees: Vec<EventAttendee>,
     pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventAttendee {
+    pub employee_id: String,
+    pub name: String,
 }
 
 impl Event {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         client_id: String,
         title: String,
@@ -132,16 +294,108 @@ impl Event {
             end_date,
             end_time,
             color,
+            attendees: Vec::new(),
+            created_at: Utc::now(),
+            updated_at: None,
+        }
+    }
+
+    pub fn starts_at(&self, tz: Tz) -> Option<DateTime<Utc>> {
+        local_start(&self.start_date, &self.start_time, tz)
+    }
+}
+
+/// Event dates and times are wall-clock values in the configured event time zone. Returns `None`
+/// when they don't parse or name a local time skipped by a DST change.
+pub fn local_start(start_date: &str, start_time: &str, tz: Tz) -> Option<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(start_date, "%Y-%m-%d").ok()?;
+    let time = if start_time.trim().is_empty() {
+        NaiveTime::MIN
+    } else {
+        parse_time(start_time)?
+    };
+    let local = tz.from_local_datetime(&date.and_time(time)).earliest()?;
+    Some(local.with_timezone(&Utc))
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    let value = value.trim();
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .ok()
+}
+
+pub const REMINDER_CHANNELS: &[&str] = &["log"];
+pub const MAX_REMINDER_MINUTES: i64 = 60 * 24 * 28;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventReminder {
+    pub id: String,
+    pub event_id: String,
+    pub client_id: String,
+    pub minutes_before: i64,
+    pub channel: String,
+    pub fire_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl EventReminder {
+    pub fn new(event: &Event, starts_at: DateTime<Utc>, minutes_before: i64, channel: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            event_id: event.id.clone(),
+            client_id: event.client_id.clone(),
+            minutes_before,
+            channel,
+            fire_at: starts_at - Duration::minutes(minutes_before),
+            sent_at: None,
             created_at: Utc::now(),
         }
     }
 }
 
+/// An unsent reminder along with the current start of its event, so the fire time can be
+/// recomputed if the event has moved since the reminder was scheduled.
+#[derive(Debug, Clone)]
+pub struct PendingReminder {
+    pub reminder: EventReminder,
+    pub event_title: String,
+    pub event_start_date: String,
+    pub event_start_time: String,
+}
+
+impl PendingReminder {
+    pub fn fire_at(&self, tz: Tz) -> Option<DateTime<Utc>> {
+        let starts_at = local_start(&self.event_start_date, &self.event_start_time, tz)?;
+        Some(starts_at - Duration::minutes(self.reminder.minutes_before))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReminderRequest {
+    pub minutes_before: i64,
+    #[serde(default)]
+    pub channel: Option<String>,
+}
+
+impl CreateReminderRequest {
+    pub fn channel(&self) -> String {
+        self.channel
+            .as_deref()
+            .map(|c| c.trim().to_lowercase())
+            .filter(|c| !c.is_empty())
+            .unwrap_or_else(|| REMINDER_CHANNELS[0].to_string())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OnboardingRequest {
     pub business_name: String,
     pub business_website: String,
     pub business_sector: String,
+    #[serde(default)]
+    pub custom_sector_text: Option<String>,
     pub revenue: String,
     pub goals: String,
     pub custom_goal_text: Option<String>,
@@ -152,6 +406,81 @@ pub struct OnboardingRequest {
     pub platforms: Vec<String>,
     pub generated_username: String,
     pub generated_password: String,
+    #[serde(default)]
+    pub auto_login: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OnboardingResponse {
+    #[serde(flatten)]
+    pub client: ClientProfile,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<LoginResponse>,
+}
+
+pub const OTHER_SECTOR: &str = "Other";
+
+pub const BUSINESS_SECTORS: &[&str] = &[
+    "Technology",
+    "Retail",
+    "Healthcare",
+    "Finance",
+    "Education",
+    "Hospitality",
+    "Manufacturing",
+    "Construction",
+    "Real Estate",
+    "Professional Services",
+    "Non-Profit",
+    OTHER_SECTOR,
+];
+
+pub const REVENUE_BANDS: &[&str] = &[
+    "Pre-revenue",
+    "Under $100K",
+    "$100K - $500K",
+    "$500K - $1M",
+    "$1M - $5M",
+    "$5M - $20M",
+    "Over $20M",
+];
+
+fn canonical_value(allowed: &[&'static str], input: &str) -> Option<&'static str> {
+    let input = input.trim();
+    allowed.iter().copied().find(|v| v.eq_ignore_ascii_case(input))
+}
+
+impl OnboardingRequest {
+    /// Resolves the sector and revenue band to their whitelisted spelling.
+    pub fn canonical_classification(&self) -> Result<(String, String), String> {
+        let sector = match canonical_value(BUSINESS_SECTORS, &self.business_sector) {
+            Some(OTHER_SECTOR) => self.custom_sector_text
+                .as_deref()
+                .map(sanitize_string)
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| "Custom sector text is required when sector is Other".to_string())?,
+            Some(sector) => sector.to_string(),
+            None => return Err(format!("Unknown business sector, expected one of: {}", BUSINESS_SECTORS.join(", "))),
+        };
+        let revenue = canonical_value(REVENUE_BANDS, &self.revenue)
+            .ok_or_else(|| format!("Unknown revenue band, expected one of: {}", REVENUE_BANDS.join(", ")))?;
+        Ok((sector, revenue.to_string()))
+    }
+}
+
+pub const MIN_PASSWORD_LENGTH: usize = 6;
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+    #[serde(default)]
+    pub logout_current: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -164,6 +493,20 @@ pub struct LoginRequest {
 pub struct LoginResponse {
     pub session_id: String,
     pub client_name: String,
+    pub refresh_token: String,
+    pub refresh_expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RefreshResponse {
+    pub session_id: String,
+    pub refresh_token: String,
+    pub refresh_expires_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -174,10 +517,232 @@ pub struct CreateEmployeeRequest {
     pub status: String,
 }
 
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const DEFAULT_MAX_PAGE_SIZE: u32 = 200;
+// The activity feed reads offset + limit rows from every table it merges, so deeper offsets are rejected.
+pub const MAX_PAGE_OFFSET: u32 = 10_000;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+impl PageQuery {
+    pub fn resolve(&self, default_size: u32, max_size: u32) -> Result<Page, String> {
+        let offset = self.offset.unwrap_or(0);
+        if offset > MAX_PAGE_OFFSET {
+            return Err(format!("Offset cannot exceed {}", MAX_PAGE_OFFSET));
+        }
+
+        Ok(Page {
+            limit: self.limit.unwrap_or(default_size).clamp(1, max_size) as i64,
+            offset: offset as i64,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Page {
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl Page {
+    pub fn all() -> Self {
+        // SQLite treats a negative LIMIT as "no limit".
+        Self { limit: -1, offset: 0 }
+    }
+
+    /// The same page with one extra row, so the response can say whether another page follows.
+    pub fn probe(&self) -> Self {
+        if self.limit < 0 {
+            return *self;
+        }
+        Self { limit: self.limit + 1, offset: self.offset }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PageInfo {
+    pub limit: i64,
+    pub offset: i64,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TaskQuery {
+    pub done: Option<bool>,
+    pub archived: Option<bool>,
+    pub priority: Option<String>,
+    pub tag: Option<String>,
+    pub created_within: Option<String>,
+    #[serde(default)]
+    pub sort: TaskSort,
+}
+
+/// `board` (the default) follows the client's priority order and then each task's reorder
+/// position, as the board and the export show them. `newest` lists tasks most recent first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskSort {
+    #[default]
+    Board,
+    Newest,
+}
+
+impl TaskQuery {
+    pub fn created_since(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let window = parse_window(self.created_within.as_deref()?).ok()?;
+        Some(now - window)
+    }
+}
+
+pub fn parse_window(raw: &str) -> Result<Duration, String> {
+    let raw = raw.trim();
+    let invalid = || format!("Invalid window {:?}, expected a number followed by h, d or w (e.g. 24h, 7d)", raw);
+    let (amount, unit) = raw.split_at(raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len()));
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    if amount <= 0 || amount > 3650 {
+        return Err(invalid());
+    }
+
+    match unit {
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => Err(invalid()),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EmployeeQuery {
+    pub min_salary: Option<f64>,
+    pub max_salary: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    #[default]
+    Strict,
+    Report,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub mode: ImportMode,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportRowError {
+    pub line: u64,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub failed: Vec<ImportRowError>,
+}
+
+pub fn parse_employee_csv(input: &str, max_salary: f64) -> Result<(Vec<CreateEmployeeRequest>, Vec<ImportRowError>), String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(input.as_bytes());
+
+    let headers = reader.headers().map_err(|e| format!("Invalid CSV header: {}", e))?.clone();
+    let mut rows = Vec::new();
+    let mut failed = Vec::new();
+
+    for result in reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map(|p| p.line()).unwrap_or(0);
+                failed.push(ImportRowError { line, error: e.to_string() });
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+
+        match record.deserialize::<CreateEmployeeRequest>(Some(&headers)) {
+            Ok(row) => match row.validate(max_salary) {
+                Ok(_) => rows.push(row),
+                Err(e) => failed.push(ImportRowError { line, error: e }),
+            },
+            Err(e) => failed.push(ImportRowError { line, error: e.to_string() }),
+        }
+    }
+
+    Ok((rows, failed))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateTaskRequest {
     pub title: String,
     pub priority: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub assignee_id: Option<String>,
+}
+
+pub const MAX_TASK_TAGS: usize = 20;
+pub const MAX_TAG_LENGTH: usize = 50;
+
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !normalized.iter().any(|t| t == tag) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+fn validate_tag(tag: &str) -> Result<(), String> {
+    if tag.trim().is_empty() {
+        return Err("Tag cannot be empty".to_string());
+    }
+    if tag.trim().chars().count() > MAX_TAG_LENGTH {
+        return Err(format!("Tags cannot be longer than {} characters", MAX_TAG_LENGTH));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagAction {
+    Add,
+    Remove,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkTagRequest {
+    pub ids: Vec<String>,
+    pub tag: String,
+    pub action: TagAction,
+}
+
+pub const DEFAULT_TASK_PRIORITIES: &[&str] = &["High", "Medium", "Low"];
+pub const MAX_TASK_PRIORITIES: usize = 10;
+
+pub fn match_priority(allowed: &[String], input: &str) -> Option<String> {
+    let input = input.trim();
+    allowed.iter().find(|p| p.eq_ignore_ascii_case(input)).cloned()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskPrioritiesRequest {
+    pub priorities: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskFacets {
+    pub priorities: Vec<String>,
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -185,11 +750,112 @@ pub struct UpdateTaskRequest {
     pub done: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveTaskRequest {
+    pub archived: bool,
+}
+
+/// `null` clears the assignee.
+#[derive(Debug, Deserialize)]
+pub struct AssignTaskRequest {
+    pub assignee_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignOutcome {
+    Updated,
+    TaskNotFound,
+    AssigneeNotFound,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkDeleteResponse {
+    pub deleted: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReorderTaskRequest {
+    pub priority: String,
+    pub position: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloneEmployeeRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TerminateEmployeeRequest {
+    pub termination_date: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateEmployeePaymentRequest {
     pub paid: bool,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateSalaryRequest {
+    pub salary: f64,
+}
+
+impl UpdateSalaryRequest {
+    pub fn validate(&self, max_salary: f64) -> Result<(), String> {
+        validate_salary(self.salary, max_salary)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SalaryChange {
+    pub employee_id: String,
+    pub old_salary: f64,
+    pub new_salary: f64,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmployeeDetail {
+    pub employee: Employee,
+    pub tasks: Vec<Task>,
+    pub events: Vec<Event>,
+    pub salary_history: Vec<SalaryChange>,
+    pub payment_history: Vec<PaymentEvent>,
+}
+
+pub const MAX_BULK_IDS: usize = 500;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkPaymentRequest {
+    pub ids: Vec<String>,
+    pub paid: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PurgeOutcome {
+    Purged,
+    StillActive,
+    NotFound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttendeeOutcome {
+    Added(usize),
+    EventNotFound,
+    EmployeeNotFound(String),
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentEvent {
+    pub employee_id: String,
+    pub paid: bool,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkUpdateResponse {
+    pub updated: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateEventRequest {
     pub title: String,
@@ -201,11 +867,46 @@ pub struct CreateEventRequest {
     pub color: String,
 }
 
+impl CreateEventRequest {
+    pub fn to_event(&self, client_id: String) -> Event {
+        Event::new(
+            client_id,
+            self.title.clone(),
+            self.description.clone().unwrap_or_default(),
+            self.start_date.clone(),
+            self.start_time.as_deref().unwrap_or_default().trim().to_string(),
+            self.end_date.clone(),
+            self.end_time.as_deref().unwrap_or_default().trim().to_string(),
+            self.color.clone(),
+        )
+    }
+}
+
+pub const MAX_EVENT_BATCH: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchCreateEventsRequest {
+    pub events: Vec<CreateEventRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddAttendeesRequest {
+    pub employee_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttendeesAddedResponse {
+    pub added: usize,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub message: String,
     pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageInfo>,
+    pub timestamp: String,
 }
 
 impl<T> ApiResponse<T> {
@@ -214,6 +915,8 @@ impl<T> ApiResponse<T> {
             success: true,
             message: message.to_string(),
             data: Some(data),
+            page: None,
+            timestamp: Utc::now().to_rfc3339(),
         }
     }
 
@@ -222,16 +925,74 @@ impl<T> ApiResponse<T> {
             success: false,
             message: message.to_string(),
             data: None,
+            page: None,
+            timestamp: Utc::now().to_rfc3339(),
         }
     }
-}
 
-#[derive(Debug)]
-pub enum AppError {
-    DbError(String),
+    pub fn error_with_data(data: T, message: &str) -> Self {
+        Self {
+            success: false,
+            message: message.to_string(),
+            data: Some(data),
+            page: None,
+            timestamp: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+impl<T> ApiResponse<Vec<T>> {
+    /// `items` is expected to come from a `page.probe()` query; the extra row is dropped and
+    /// reported as `has_more` instead.
+    pub fn paged(mut items: Vec<T>, page: Page, message: &str) -> Self {
+        let has_more = page.limit >= 0 && items.len() as i64 > page.limit;
+        if has_more {
+            items.truncate(page.limit as usize);
+        }
+        Self {
+            page: Some(PageInfo { limit: page.limit, offset: page.offset, has_more }),
+            ..Self::success(items, message)
+        }
+    }
+}
+
+pub const INVALID_ID_MESSAGE: &str = "Invalid id format";
+
+// Path ids are checked to be UUIDs during extraction, so malformed ids never reach the database.
+#[derive(Debug, Clone)]
+pub struct EntityId(String);
+
+impl<'de> Deserialize<'de> for EntityId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        Uuid::parse_str(raw.trim())
+            .map(|id| EntityId(id.to_string()))
+            .map_err(|_| serde::de::Error::custom(INVALID_ID_MESSAGE))
+    }
+}
+
+impl std::ops::Deref for EntityId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum AppError {
+    DbError(String),
     NotFound(String),
     InvalidInput(String),
     Unauthorized,
+    Forbidden(String),
     InternalError,
 }
 
@@ -242,6 +1003,7 @@ impl fmt::Display for AppError {
             AppError::NotFound(msg) => write!(f, "Not Found: {}", msg),
             AppError::InvalidInput(msg) => write!(f, "Invalid Input: {}", msg),
             AppError::Unauthorized => write!(f, "Unauthorized Access"),
+            AppError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             AppError::InternalError => write!(f, "Internal Server Error"),
         }
     }
@@ -249,22 +1011,15 @@ impl fmt::Display for AppError {
 
 impl std::error::Error for AppError {}
 
+#[allow(dead_code)]
+#[derive(Default)]
 pub struct ServiceMetrics {
     pub active_sessions: u32,
     pub total_requests: u64,
     pub uptime_seconds: u64,
 }
 
-impl Default for ServiceMetrics {
-    fn default() -> Self {
-        Self {
-            active_sessions: 0,
-            total_requests: 0,
-            uptime_seconds: 0,
-        }
-    }
-}
-
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemStatus {
     pub status: String,
@@ -294,12 +1049,46 @@ impl Validatable for OnboardingRequest {
         if self.email.is_empty() || !self.email.contains('@') {
             return Err("Valid email is required".to_string());
         }
+        self.canonical_classification()?;
         if self.generated_username.len() < 3 {
             return Err("Username too short".to_string());
         }
-        if self.generated_password.len() < 6 {
+        if self.generated_password.len() < MIN_PASSWORD_LENGTH {
+            return Err("Password too short".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for RefreshRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.refresh_token.trim().is_empty() {
+            return Err("Refresh token cannot be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for ChangePasswordRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.current_password.is_empty() {
+            return Err("Current password cannot be empty".to_string());
+        }
+        if self.new_password.len() < MIN_PASSWORD_LENGTH {
             return Err("Password too short".to_string());
         }
+        if self.new_password == self.current_password {
+            return Err("New password must differ from the current password".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for DeleteAccountRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.password.is_empty() {
+            return Err("Password is required to delete the account".to_string());
+        }
         Ok(())
     }
 }
@@ -316,13 +1105,171 @@ impl Validatable for LoginRequest {
     }
 }
 
-impl Validatable for CreateEmployeeRequest {
-    fn validate(&self) -> Result<(), String> {
+// Salary limits come from Config, so these take the cap explicitly instead of implementing Validatable.
+impl CreateEmployeeRequest {
+    pub fn validate(&self, max_salary: f64) -> Result<(), String> {
         if self.name.is_empty() {
             return Err("Employee name is required".to_string());
         }
-        if self.salary < 0.0 {
-            return Err("Salary cannot be negative".to_string());
+        validate_salary(self.salary, max_salary)
+    }
+}
+
+pub const DEFAULT_MAX_SALARY: f64 = 100_000_000.0;
+
+pub fn validate_salary(salary: f64, max_salary: f64) -> Result<(), String> {
+    if !salary.is_finite() {
+        return Err("Salary must be a finite number".to_string());
+    }
+    if salary < 0.0 {
+        return Err("Salary cannot be negative".to_string());
+    }
+    if salary > max_salary {
+        return Err(format!("Salary cannot exceed {}", format_money(max_salary)));
+    }
+    Ok(())
+}
+
+impl Validatable for CreateTaskRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.tags.len() > MAX_TASK_TAGS {
+            return Err(format!("A task cannot have more than {} tags", MAX_TASK_TAGS));
+        }
+        for tag in self.tags.iter().filter(|t| !t.trim().is_empty()) {
+            validate_tag(tag)?;
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for BulkTagRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.ids.is_empty() {
+            return Err("At least one task id is required".to_string());
+        }
+        if self.ids.len() > MAX_BULK_IDS {
+            return Err(format!("Cannot tag more than {} tasks at once", MAX_BULK_IDS));
+        }
+        validate_tag(&self.tag)
+    }
+}
+
+impl Validatable for TaskPrioritiesRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.priorities.is_empty() {
+            return Err("At least one priority is required".to_string());
+        }
+        if self.priorities.len() > MAX_TASK_PRIORITIES {
+            return Err(format!("Cannot configure more than {} priorities", MAX_TASK_PRIORITIES));
+        }
+        for (i, priority) in self.priorities.iter().enumerate() {
+            let priority = priority.trim();
+            if priority.is_empty() {
+                return Err("Priorities cannot be empty".to_string());
+            }
+            if priority.contains(',') {
+                return Err("Priorities cannot contain commas".to_string());
+            }
+            if self.priorities[..i].iter().any(|p| p.trim().eq_ignore_ascii_case(priority)) {
+                return Err(format!("Duplicate priority: {}", priority));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for TaskQuery {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(window) = &self.created_within {
+            parse_window(window)?;
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for EmployeeQuery {
+    fn validate(&self) -> Result<(), String> {
+        for bound in [self.min_salary, self.max_salary].into_iter().flatten() {
+            if !bound.is_finite() {
+                return Err("Salary bounds must be finite numbers".to_string());
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_salary, self.max_salary) {
+            if min > max {
+                return Err("min_salary cannot be greater than max_salary".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for CloneEmployeeRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Employee name is required".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for TerminateEmployeeRequest {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(date) = &self.termination_date {
+            if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+                return Err("Termination date must be in YYYY-MM-DD format".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for ReorderTaskRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.priority.trim().is_empty() {
+            return Err("Priority is required".to_string());
+        }
+        if !self.position.is_finite() {
+            return Err("Position must be a finite number".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for BulkPaymentRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.ids.is_empty() {
+            return Err("At least one employee id is required".to_string());
+        }
+        if self.ids.len() > MAX_BULK_IDS {
+            return Err(format!("Cannot update more than {} employees at once", MAX_BULK_IDS));
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for CreateReminderRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.minutes_before <= 0 {
+            return Err("minutes_before must be positive".to_string());
+        }
+        if self.minutes_before > MAX_REMINDER_MINUTES {
+            return Err(format!("minutes_before cannot exceed {}", MAX_REMINDER_MINUTES));
+        }
+        let channel = self.channel();
+        if !REMINDER_CHANNELS.contains(&channel.as_str()) {
+            return Err(format!("Channel must be one of: {}", REMINDER_CHANNELS.join(", ")));
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for AddAttendeesRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.employee_ids.is_empty() {
+            return Err("At least one employee id is required".to_string());
+        }
+        if self.employee_ids.len() > MAX_BULK_IDS {
+            return Err(format!("Cannot add more than {} attendees at once", MAX_BULK_IDS));
         }
         Ok(())
     }
@@ -336,13 +1283,164 @@ impl Validatable for CreateEventRequest {
         if self.start_date.is_empty() || self.end_date.is_empty() {
             return Err("Start and end dates are required".to_string());
         }
+        let start_time = validate_event_time(&self.start_time, "Start time")?;
+        let end_time = validate_event_time(&self.end_time, "End time")?;
+        if let (Some(start_time), Some(end_time)) = (start_time, end_time) {
+            if self.start_date == self.end_date && end_time < start_time {
+                return Err("End time cannot be before start time".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CreateEventRequest {
+    /// Batch imports additionally require ISO dates in order and a hex color. Single event
+    /// creation keeps accepting whatever the calendar UI already sends.
+    pub fn validate_strict(&self) -> Result<(), String> {
+        self.validate()?;
+        let start = NaiveDate::parse_from_str(&self.start_date, "%Y-%m-%d")
+            .map_err(|_| "Start date must be in YYYY-MM-DD format".to_string())?;
+        let end = NaiveDate::parse_from_str(&self.end_date, "%Y-%m-%d")
+            .map_err(|_| "End date must be in YYYY-MM-DD format".to_string())?;
+        if end < start {
+            return Err("End date cannot be before start date".to_string());
+        }
+        if !is_hex_color(&self.color) {
+            return Err("Color must be a hex value like #1A2B3C".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Validatable for BatchCreateEventsRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.events.is_empty() {
+            return Err("At least one event is required".to_string());
+        }
+        if self.events.len() > MAX_EVENT_BATCH {
+            return Err(format!("Cannot create more than {} events at once", MAX_EVENT_BATCH));
+        }
+        for (i, event) in self.events.iter().enumerate() {
+            event.validate_strict().map_err(|e| format!("Event {}: {}", i + 1, e))?;
+        }
         Ok(())
     }
 }
 
+fn parse_hh_mm(value: &str) -> Option<NaiveTime> {
+    if value.len() != 5 || value.as_bytes()[2] != b':' {
+        return None;
+    }
+    NaiveTime::parse_from_str(value, "%H:%M").ok()
+}
+
+fn validate_event_time(value: &Option<String>, field: &str) -> Result<Option<NaiveTime>, String> {
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(time) => parse_hh_mm(time)
+            .map(Some)
+            .ok_or_else(|| format!("{} must be in 24-hour HH:MM format", field)),
+    }
+}
+
+fn is_hex_color(value: &str) -> bool {
+    match value.strip_prefix('#') {
+        Some(hex) => (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct SessionData {
+    pub token: String,
+    pub client_id: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub refresh_family: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RefreshTokenData {
+    pub token_hash: String,
+    pub family_id: String,
     pub client_id: String,
+    pub created_at: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
+    pub rotated_at: Option<DateTime<Utc>>,
+    pub revoked: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PayrollGroup {
+    pub status: String,
+    pub employees: i64,
+    pub salary_total: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PayrollBreakdown {
+    pub groups: Vec<PayrollGroup>,
+    pub salary_total: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActivityItem {
+    pub entity_type: String,
+    pub entity_id: String,
+    pub title: String,
+    pub action: String,
+    pub at: DateTime<Utc>,
+}
+
+impl ActivityItem {
+    pub fn new(entity: &impl EntityIdentity, title: &str, action: &str, at: DateTime<Utc>) -> Self {
+        Self {
+            entity_type: entity.get_type().to_string(),
+            entity_id: entity.get_id().to_string(),
+            title: title.to_string(),
+            action: action.to_string(),
+            at,
+        }
+    }
+}
+
+impl From<&Employee> for ActivityItem {
+    fn from(employee: &Employee) -> Self {
+        match employee.updated_at {
+            Some(updated_at) => ActivityItem::new(employee, &employee.name, "updated", updated_at),
+            None => ActivityItem::new(employee, &employee.name, "created", employee.created_at),
+        }
+    }
+}
+
+impl From<&Task> for ActivityItem {
+    fn from(task: &Task) -> Self {
+        // Completing a task also stamps updated_at, so "completed" wins unless something changed since.
+        match (task.completed_at, task.updated_at) {
+            (Some(completed_at), updated_at) if updated_at.is_none_or(|u| u <= completed_at) => {
+                ActivityItem::new(task, &task.title, "completed", completed_at)
+            },
+            (_, Some(updated_at)) => ActivityItem::new(task, &task.title, "updated", updated_at),
+            (_, None) => ActivityItem::new(task, &task.title, "created", task.created_at),
+        }
+    }
+}
+
+impl From<&Event> for ActivityItem {
+    fn from(event: &Event) -> Self {
+        match event.updated_at {
+            Some(updated_at) => ActivityItem::new(event, &event.title, "updated", updated_at),
+            None => ActivityItem::new(event, &event.title, "created", event.created_at),
+        }
+    }
+}
+
+// Each source must already be limited to its newest `offset + limit` rows for the page to be exact.
+pub fn merge_activity(mut items: Vec<ActivityItem>, page: &Page) -> Vec<ActivityItem> {
+    items.sort_by(|a, b| b.at.cmp(&a.at).then_with(|| a.entity_id.cmp(&b.entity_id)));
+    let take = if page.limit < 0 { usize::MAX } else { page.limit as usize };
+    items.into_iter().skip(page.offset as usize).take(take).collect()
 }
 
 #[derive(Debug, Serialize)]
@@ -350,9 +1448,195 @@ pub struct DashboardStats {
     pub total_employees: i64,
     pub monthly_payroll: f64,
     pub active_tasks: i64,
+    pub tasks_completion_rate: f64,
     pub total_events: i64,
 }
 
+pub fn completion_rate(done: i64, total: i64) -> f64 {
+    if total <= 0 {
+        return 0.0;
+    }
+    done as f64 / total as f64
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntegrityReport {
+    pub ok: bool,
+    pub problems: Vec<String>,
+}
+
+impl IntegrityReport {
+    pub fn from_messages(messages: Vec<String>) -> Self {
+        let ok = messages.len() == 1 && messages[0] == "ok";
+        Self {
+            ok,
+            problems: if ok { Vec::new() } else { messages },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct VacuumReport {
+    pub size_before_bytes: u64,
+    pub size_after_bytes: u64,
+    pub reclaimed_bytes: u64,
+}
+
+pub const MAX_DOCUMENT_BYTES: usize = 10 * 1024 * 1024;
+pub const DEFAULT_DOCUMENT_TYPE: &str = "application/octet-stream";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Document {
+    pub id: String,
+    pub client_id: String,
+    pub name: String,
+    pub size_bytes: i64,
+    pub file_type: String,
+    pub task_id: Option<String>,
+    pub event_id: Option<String>,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+impl Document {
+    pub fn new(client_id: String, query: &UploadDocumentQuery, file_type: String, size_bytes: usize) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            client_id,
+            name: query.name.trim().to_string(),
+            size_bytes: size_bytes as i64,
+            file_type,
+            task_id: query.task_id.clone(),
+            event_id: query.event_id.clone(),
+            uploaded_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadDocumentQuery {
+    pub name: String,
+    pub task_id: Option<String>,
+    pub event_id: Option<String>,
+}
+
+impl Validatable for UploadDocumentQuery {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Document name is required".to_string());
+        }
+        if self.name.chars().count() > 255 {
+            return Err("Document name cannot be longer than 255 characters".to_string());
+        }
+        if self.name.chars().any(char::is_control) {
+            return Err("Document name cannot contain control characters".to_string());
+        }
+        if self.task_id.is_some() && self.event_id.is_some() {
+            return Err("A document can be attached to a task or an event, not both".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DataSummary {
+    pub employees: i64,
+    pub tasks: i64,
+    pub events: i64,
+    pub documents: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountDeletionImpact {
+    pub employees: i64,
+    pub tasks: i64,
+    pub events: i64,
+    pub documents: i64,
+    pub sessions: i64,
+}
+
+pub struct SandboxSeed {
+    pub employees: Vec<Employee>,
+    pub tasks: Vec<Task>,
+    pub events: Vec<Event>,
+}
+
+impl SandboxSeed {
+    pub fn demo(client_id: &str, priorities: &[String], today: NaiveDate) -> Self {
+        let employees: Vec<Employee> = [
+            ("Alex Morgan", "Operations Manager", 5200.0),
+            ("Jamie Chen", "Marketing Lead", 4600.0),
+            ("Sam Patel", "Sales Associate", 3100.0),
+        ]
+        .iter()
+        .map(|(name, title, salary)| Employee::new(client_id.to_string(), name.to_string(), title.to_string(), *salary, "Active".to_string()))
+        .collect();
+
+        let priority = |i: usize| priorities.get(i).or(priorities.last()).cloned().unwrap_or_default();
+        let tasks = [
+            ("Review quarterly budget", 0, Some(0), false),
+            ("Plan product launch campaign", 0, Some(1), false),
+            ("Follow up with new leads", 1, Some(2), false),
+            ("Update employee handbook", 2, None, true),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, (title, p, assignee, done))| {
+            let mut task = Task::new(client_id.to_string(), title.to_string(), priority(*p));
+            task.position = (i + 1) as f64;
+            task.assignee_id = assignee.map(|a| employees[a].id.clone());
+            task.done = *done;
+            task.completed_at = if *done { Some(task.created_at) } else { None };
+            task
+        })
+        .collect();
+
+        let day = |offset: i64| (today + Duration::days(offset)).format("%Y-%m-%d").to_string();
+        let events = vec![
+            Event::new(client_id.to_string(), "Team standup".to_string(), "Weekly sync".to_string(), day(1), "09:00".to_string(), day(1), "09:30".to_string(), "#3B82F6".to_string()),
+            Event::new(client_id.to_string(), "Client demo".to_string(), "Product walkthrough".to_string(), day(7), "14:00".to_string(), day(7), "15:00".to_string(), "#10B981".to_string()),
+        ];
+
+        Self { employees, tasks, events }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrendMetric {
+    pub current: f64,
+    pub previous: f64,
+    pub delta: f64,
+    pub percent_change: Option<f64>,
+}
+
+impl TrendMetric {
+    pub fn new(current: f64, previous: f64) -> Self {
+        let percent_change = if previous == 0.0 {
+            None
+        } else {
+            Some((current - previous) / previous * 100.0)
+        };
+
+        Self {
+            current,
+            previous,
+            delta: current - previous,
+            percent_change,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DashboardTrends {
+    pub period_days: i64,
+    pub current_period_start: DateTime<Utc>,
+    pub previous_period_start: DateTime<Utc>,
+    pub employees: TrendMetric,
+    pub payroll: TrendMetric,
+    pub tasks_completed: TrendMetric,
+    pub events: TrendMetric,
+}
+
+#[allow(dead_code)]
 #[derive(Debug, Serialize)]
 pub struct DocumentMetadata {
     pub id: String,
@@ -363,6 +1647,7 @@ pub struct DocumentMetadata {
 }
 
 impl DocumentMetadata {
+    #[allow(dead_code)]
     pub fn mock_list() -> Vec<Self> {
         vec![
             Self {
@@ -383,6 +1668,7 @@ impl DocumentMetadata {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Serialize)]
 pub struct SpreadsheetMetadata {
     pub id: String,
@@ -392,6 +1678,7 @@ pub struct SpreadsheetMetadata {
 }
 
 impl SpreadsheetMetadata {
+    #[allow(dead_code)]
     pub fn mock_list() -> Vec<Self> {
         vec![
             Self {
@@ -410,6 +1697,7 @@ impl SpreadsheetMetadata {
     }
 }
 
+#[allow(dead_code)]
 pub fn generate_secure_token() -> String {
     let mut token = String::new();
     for _ in 0..32 {
@@ -431,6 +1719,7 @@ pub fn sanitize_string(input: &str) -> String {
     input.replace('<', "&lt;").replace('>', "&gt;").trim().to_string()
 }
 
+#[allow(dead_code)]
 #[derive(Clone, Copy, Debug)]
 pub enum LogLevel {
     Info,
@@ -440,8 +1729,12 @@ pub enum LogLevel {
 }
 
 pub fn log_event(level: LogLevel, message: &str) {
-    let timestamp = Utc::now().to_rfc3339();
-    println!("[{}] {:?}: {}", timestamp, level, message);
+    match level {
+        LogLevel::Info => tracing::info!("{}", message),
+        LogLevel::Warn => tracing::warn!("{}", message),
+        LogLevel::Error => tracing::error!("{}", message),
+        LogLevel::Debug => tracing::debug!("{}", message),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -476,3 +1769,261 @@ impl EntityIdentity for Event {
     fn get_id(&self) -> &str { &self.id }
     fn get_type(&self) -> &str { "Event" }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn onboarding(sector: &str, custom_sector: Option<&str>, revenue: &str) -> OnboardingRequest {
+        OnboardingRequest {
+            business_name: "Acme".to_string(),
+            business_website: "https://acme.test".to_string(),
+            business_sector: sector.to_string(),
+            custom_sector_text: custom_sector.map(str::to_string),
+            revenue: revenue.to_string(),
+            goals: "Grow".to_string(),
+            custom_goal_text: None,
+            email: "owner@acme.test".to_string(),
+            job_title: "Owner".to_string(),
+            services: Vec::new(),
+            other_service_text: None,
+            platforms: Vec::new(),
+            generated_username: "acme".to_string(),
+            generated_password: "secret123".to_string(),
+            auto_login: false,
+        }
+    }
+
+    #[test]
+    fn classification_is_canonicalized_case_insensitively() {
+        let req = onboarding("  real estate ", None, "under $100k");
+        assert_eq!(req.canonical_classification().unwrap(), ("Real Estate".to_string(), "Under $100K".to_string()));
+        assert!(req.validate().is_ok());
+    }
+
+    #[test]
+    fn classification_outside_whitelist_is_rejected() {
+        let err = onboarding("Crypto", None, "Pre-revenue").validate().unwrap_err();
+        assert!(err.starts_with("Unknown business sector"));
+
+        let err = onboarding("Retail", None, "A lot").validate().unwrap_err();
+        assert!(err.starts_with("Unknown revenue band"));
+    }
+
+    #[test]
+    fn other_sector_uses_custom_text() {
+        let req = onboarding("other", Some("  Beekeeping "), "Pre-revenue");
+        assert_eq!(req.canonical_classification().unwrap().0, "Beekeeping");
+
+        let err = onboarding("Other", Some("   "), "Pre-revenue").validate().unwrap_err();
+        assert_eq!(err, "Custom sector text is required when sector is Other");
+    }
+
+    fn event_request(start_date: &str, end_date: &str, color: &str) -> CreateEventRequest {
+        CreateEventRequest {
+            title: "Standup".to_string(),
+            description: None,
+            start_date: start_date.to_string(),
+            start_time: Some("09:00".to_string()),
+            end_date: end_date.to_string(),
+            end_time: Some("09:15".to_string()),
+            color: color.to_string(),
+        }
+    }
+
+    #[test]
+    fn single_events_keep_lenient_date_and_color_checks() {
+        assert!(event_request("03/14/2030", "03/14/2030", "blue").validate().is_ok());
+
+        let mut inverted = event_request("2030-03-14", "2030-03-14", "#fff");
+        inverted.end_time = Some("08:00".to_string());
+        assert_eq!(inverted.validate().unwrap_err(), "End time cannot be before start time");
+    }
+
+    #[test]
+    fn event_times_must_be_24_hour_hh_mm() {
+        let mut request = event_request("2030-03-14", "2030-03-14", "#fff");
+        request.start_time = Some("14:30".to_string());
+        request.end_time = Some("15:00".to_string());
+        assert!(request.validate().is_ok());
+
+        request.start_time = Some("25:00".to_string());
+        assert_eq!(request.validate().unwrap_err(), "Start time must be in 24-hour HH:MM format");
+
+        request.start_time = None;
+        request.end_time = Some("  ".to_string());
+        assert!(request.validate().is_ok());
+        assert_eq!(request.to_event("c1".to_string()).start_time, "");
+    }
+
+    #[test]
+    fn batch_events_require_iso_dates_and_hex_colors() {
+        assert!(event_request("2030-03-14", "2030-03-15", "#1A2B3C").validate_strict().is_ok());
+        assert_eq!(
+            event_request("03/14/2030", "03/14/2030", "#fff").validate_strict().unwrap_err(),
+            "Start date must be in YYYY-MM-DD format",
+        );
+        assert_eq!(
+            event_request("2030-03-15", "2030-03-14", "#fff").validate_strict().unwrap_err(),
+            "End date cannot be before start date",
+        );
+        assert_eq!(
+            event_request("2030-03-14", "2030-03-14", "blue").validate_strict().unwrap_err(),
+            "Color must be a hex value like #1A2B3C",
+        );
+    }
+
+    #[test]
+    fn page_query_defaults_clamps_and_rejects_deep_offsets() {
+        let page = PageQuery::default().resolve(50, 200).unwrap();
+        assert_eq!((page.limit, page.offset), (50, 0));
+
+        let page = PageQuery { limit: Some(0), offset: Some(MAX_PAGE_OFFSET) }.resolve(50, 200).unwrap();
+        assert_eq!((page.limit, page.offset), (1, MAX_PAGE_OFFSET as i64));
+
+        let page = PageQuery { limit: Some(1000), offset: Some(20) }.resolve(50, 200).unwrap();
+        assert_eq!((page.limit, page.offset), (200, 20));
+
+        assert!(PageQuery { limit: None, offset: Some(MAX_PAGE_OFFSET + 1) }.resolve(50, 200).is_err());
+        assert!(PageQuery { limit: None, offset: Some(u32::MAX) }.resolve(50, 200).is_err());
+    }
+
+    #[test]
+    fn paged_responses_report_whether_more_rows_follow() {
+        let page = Page { limit: 2, offset: 4 };
+        assert_eq!(page.probe().limit, 3);
+
+        let json = serde_json::to_value(ApiResponse::paged(vec![1, 2, 3], page, "ok")).unwrap();
+        assert_eq!(json["data"], serde_json::json!([1, 2]));
+        assert_eq!(json["page"], serde_json::json!({ "limit": 2, "offset": 4, "has_more": true }));
+
+        let json = serde_json::to_value(ApiResponse::paged(vec![1], page, "ok")).unwrap();
+        assert_eq!(json["page"]["has_more"], false);
+        assert!(serde_json::to_value(ApiResponse::success(1, "ok")).unwrap().get("page").is_none());
+    }
+
+    #[test]
+    fn api_responses_carry_an_rfc3339_timestamp() {
+        let before = Utc::now() - Duration::seconds(1);
+        for response in [ApiResponse::success(1, "ok"), ApiResponse::error("nope"), ApiResponse::error_with_data(2, "partial")] {
+            let json = serde_json::to_value(&response).unwrap();
+            let timestamp = DateTime::parse_from_rfc3339(json["timestamp"].as_str().unwrap()).unwrap();
+            assert!(timestamp >= before);
+        }
+    }
+
+    #[test]
+    fn cloning_copies_role_and_status_but_not_termination() {
+        let template = Employee::new("c1".to_string(), "Ada".to_string(), "Engineer".to_string(), 5000.0, "On Leave".to_string());
+        let copy = template.clone_as("Grace".to_string());
+        assert_ne!(copy.id, template.id);
+        assert_eq!((copy.name.as_str(), copy.title.as_str(), copy.salary, copy.status.as_str()), ("Grace", "Engineer", 5000.0, "On Leave"));
+
+        let mut leaver = template.clone();
+        leaver.status = TERMINATED_STATUS.to_string();
+        leaver.termination_date = Some("2030-01-31".to_string());
+        let copy = leaver.clone_as("Linus".to_string());
+        assert_eq!(copy.status, ACTIVE_STATUS);
+        assert!(copy.termination_date.is_none());
+        assert!(!copy.paid);
+    }
+
+    #[test]
+    fn salary_is_checked_against_the_given_cap() {
+        assert!(validate_salary(10_000.0, 10_000.0).is_ok());
+        assert_eq!(validate_salary(10_000.01, 10_000.0).unwrap_err(), "Salary cannot exceed $10000.00");
+        assert_eq!(validate_salary(-1.0, 10_000.0).unwrap_err(), "Salary cannot be negative");
+        assert_eq!(validate_salary(f64::NAN, 10_000.0).unwrap_err(), "Salary must be a finite number");
+        assert_eq!(validate_salary(f64::INFINITY, 10_000.0).unwrap_err(), "Salary must be a finite number");
+        assert_eq!(validate_salary(f64::NEG_INFINITY, 10_000.0).unwrap_err(), "Salary must be a finite number");
+        assert!(UpdateSalaryRequest { salary: 50_000.0 }.validate(DEFAULT_MAX_SALARY).is_ok());
+        assert!(UpdateSalaryRequest { salary: 50_000.0 }.validate(10_000.0).is_err());
+    }
+
+    #[test]
+    fn integrity_report_is_ok_only_for_a_single_ok_row() {
+        let report = IntegrityReport::from_messages(vec!["ok".to_string()]);
+        assert!(report.ok);
+        assert!(report.problems.is_empty());
+
+        let messages = vec!["row 3 missing from index idx_tasks".to_string(), "wrong # of entries".to_string()];
+        let report = IntegrityReport::from_messages(messages.clone());
+        assert!(!report.ok);
+        assert_eq!(report.problems, messages);
+    }
+
+    fn priorities(values: &[&str]) -> TaskPrioritiesRequest {
+        TaskPrioritiesRequest { priorities: values.iter().map(|p| p.to_string()).collect() }
+    }
+
+    #[test]
+    fn task_priorities_reject_commas_duplicates_and_blanks() {
+        assert!(priorities(&["Now", "Soon"]).validate().is_ok());
+        assert_eq!(priorities(&["Now", "Later, maybe"]).validate().unwrap_err(), "Priorities cannot contain commas");
+        assert_eq!(priorities(&["Now", " now "]).validate().unwrap_err(), "Duplicate priority: now");
+        assert_eq!(priorities(&["Now", "  "]).validate().unwrap_err(), "Priorities cannot be empty");
+        assert_eq!(priorities(&[]).validate().unwrap_err(), "At least one priority is required");
+    }
+
+    #[test]
+    fn markdown_escaping_leaves_plain_punctuation_alone() {
+        assert_eq!(escape_markdown("Q4 plan - draft (v2.1)! #3 + {x} | y"), "Q4 plan - draft (v2.1)! #3 + {x} | y");
+        assert_eq!(escape_markdown("fix *all* the [bugs] in `main_loop` <b>"), "fix \\*all\\* the \\[bugs\\] in \\`main\\_loop\\` \\<b>");
+        assert_eq!(escape_markdown("a\\b ~~c~~\nd"), "a\\\\b \\~\\~c\\~\\~ d");
+    }
+
+    #[test]
+    fn markdown_headings_keep_trailing_hashes() {
+        assert_eq!(escape_markdown_heading("C#"), "C#");
+        assert_eq!(escape_markdown_heading("Tier ##"), "Tier \\##");
+        assert_eq!(escape_markdown_heading("###"), "\\###");
+    }
+
+    #[test]
+    fn markdown_export_lists_tasks_under_priority_headings() {
+        let mut done = Task::new("c1".to_string(), "Ship v1.0 - *finally*".to_string(), "High".to_string());
+        done.done = true;
+        let open = Task::new("c1".to_string(), "Write docs".to_string(), "High".to_string());
+        let columns = group_tasks_by_priority(vec!["High".to_string(), "Low".to_string()], vec![done, open]);
+        assert_eq!(
+            tasks_to_markdown(&columns),
+            "# Tasks\n\n## High\n\n- [x] Ship v1.0 - \\*finally\\*\n- [ ] Write docs\n",
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct CapturedLog(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLog {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn debug_events_are_filtered_at_the_default_level() {
+        let captured = CapturedLog::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::new(crate::config::Config::default().log_filter))
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            log_event(LogLevel::Debug, "debug detail");
+            log_event(LogLevel::Info, "info summary");
+            log_event(LogLevel::Warn, "warn notice");
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert!(!output.contains("debug detail"));
+        assert!(output.contains("info summary"));
+        assert!(output.contains("warn notice"));
+    }
+}
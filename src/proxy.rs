@@ -0,0 +1,123 @@
+use actix_web::HttpRequest;
+use std::net::{IpAddr, SocketAddr};
+
+pub fn parse_trusted_proxies(raw: &str) -> Result<Vec<IpAddr>, String> {
+    raw.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|_| format!("Invalid trusted proxy address: {}", s)))
+        .collect()
+}
+
+pub fn client_ip(req: &HttpRequest, trusted: &[IpAddr]) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    Some(resolve_client_ip(peer, forwarded_chain(req), trusted))
+}
+
+// Forwarded headers are only believed when the connection itself comes from a trusted proxy.
+// The chain is walked right to left so a client cannot spoof its address by prepending entries,
+// and the walk stops at the first hop that isn't an address since nothing left of it can be trusted.
+pub fn resolve_client_ip(peer: IpAddr, chain: Vec<Option<IpAddr>>, trusted: &[IpAddr]) -> IpAddr {
+    if !trusted.contains(&peer) {
+        return peer;
+    }
+
+    let mut client = peer;
+    for hop in chain.into_iter().rev() {
+        let Some(hop) = hop else { break };
+        client = hop;
+        if !trusted.contains(&hop) {
+            break;
+        }
+    }
+    client
+}
+
+fn forwarded_chain(req: &HttpRequest) -> Vec<Option<IpAddr>> {
+    let headers = req.headers();
+
+    let xff: Vec<Option<IpAddr>> = headers
+        .get_all("X-Forwarded-For")
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .map(parse_forwarded_addr)
+        .collect();
+    if !xff.is_empty() {
+        return xff;
+    }
+
+    headers
+        .get_all("Forwarded")
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split([',', ';']))
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            if key.trim().eq_ignore_ascii_case("for") {
+                Some(parse_forwarded_addr(value))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn parse_forwarded_addr(value: &str) -> Option<IpAddr> {
+    let value = value.trim().trim_matches('"');
+    if let Ok(ip) = value.parse::<IpAddr>() {
+        return Some(ip);
+    }
+    if let Ok(addr) = value.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+    value.strip_prefix('[')?.split(']').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn request_from(peer: &str, header: (&str, &str)) -> HttpRequest {
+        TestRequest::default()
+            .peer_addr(SocketAddr::new(ip(peer), 40000))
+            .insert_header(header)
+            .to_http_request()
+    }
+
+    #[test]
+    fn forwarded_headers_are_ignored_from_untrusted_peers() {
+        let req = request_from("203.0.113.9", ("X-Forwarded-For", "198.51.100.1"));
+        assert_eq!(client_ip(&req, &[ip("10.0.0.1")]), Some(ip("203.0.113.9")));
+    }
+
+    #[test]
+    fn rightmost_untrusted_hop_is_the_client() {
+        let trusted = [ip("10.0.0.1"), ip("10.0.0.2")];
+        let req = request_from("10.0.0.1", ("X-Forwarded-For", "1.1.1.1, 198.51.100.1, 10.0.0.2"));
+        assert_eq!(client_ip(&req, &trusted), Some(ip("198.51.100.1")));
+
+        let req = request_from("10.0.0.1", ("Forwarded", "for=198.51.100.1;proto=https, for=\"[2001:db8::1]:4711\""));
+        assert_eq!(client_ip(&req, &trusted), Some(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn walk_stops_at_the_first_unparseable_hop() {
+        let trusted = [ip("10.0.0.1"), ip("10.0.0.2")];
+        let req = request_from("10.0.0.1", ("X-Forwarded-For", "198.51.100.1, garbage, 10.0.0.2"));
+        assert_eq!(client_ip(&req, &trusted), Some(ip("10.0.0.2")));
+
+        let chain = vec![Some(ip("198.51.100.1")), None];
+        assert_eq!(resolve_client_ip(ip("10.0.0.1"), chain, &trusted), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn fully_trusted_chain_resolves_to_its_leftmost_hop() {
+        let trusted = [ip("10.0.0.1"), ip("10.0.0.2")];
+        let chain = vec![Some(ip("10.0.0.2"))];
+        assert_eq!(resolve_client_ip(ip("10.0.0.1"), chain, &trusted), ip("10.0.0.2"));
+    }
+}
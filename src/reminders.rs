@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::models::{log_event, PendingReminder, LogLevel};
+use crate::storage::Storage;
+
+// The dispatcher makes blocking SQLite calls, so it gets its own thread rather than a task on
+// the actix runtime.
+pub fn spawn_scheduler(storage: Arc<Storage>, poll_interval: Duration, tz: Tz) -> std::io::Result<()> {
+    std::thread::Builder::new()
+        .name("reminders".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(poll_interval);
+            if let Err(e) = dispatch_due_reminders(&storage, Utc::now(), tz) {
+                log_event(LogLevel::Error, &format!("Failed to dispatch event reminders: {}", e));
+            }
+        })
+        .map(|_| ())
+}
+
+/// Fire times are recomputed from each event's current start, so reminders follow events that
+/// were moved after the reminder was created.
+pub fn dispatch_due_reminders(storage: &Storage, now: DateTime<Utc>, tz: Tz) -> rusqlite::Result<usize> {
+    let mut sent = 0;
+    for pending in storage.get_pending_reminders()? {
+        let fire_at = match pending.fire_at(tz) {
+            Some(fire_at) => fire_at,
+            None => {
+                log_event(LogLevel::Warn, &format!("Event for reminder {} has no valid start, skipping", pending.reminder.id));
+                continue;
+            }
+        };
+        if fire_at != pending.reminder.fire_at {
+            storage.reschedule_reminder(&pending.reminder.id, fire_at)?;
+        }
+        if fire_at > now {
+            continue;
+        }
+        deliver(&pending);
+        sent += storage.mark_reminder_sent(&pending.reminder.id, now)?;
+    }
+    Ok(sent)
+}
+
+fn deliver(due: &PendingReminder) {
+    let reminder = &due.reminder;
+    match reminder.channel.as_str() {
+        "log" => log_event(
+            LogLevel::Info,
+            &format!(
+                "Reminder for client {}: \"{}\" starts in {} minutes",
+                reminder.client_id, due.event_title, reminder.minutes_before
+            ),
+        ),
+        other => log_event(LogLevel::Warn, &format!("Unknown reminder channel {} for reminder {}", other, reminder.id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Client, Event, EventReminder};
+    use chrono::TimeZone;
+
+    fn seed_reminder(storage: &Storage, start_date: &str, start_time: &str, minutes_before: i64) -> Event {
+        let client = Client::new(
+            "Acme".to_string(),
+            "https://acme.test".to_string(),
+            "Retail".to_string(),
+            "Pre-revenue".to_string(),
+            "Grow".to_string(),
+            "owner@acme.test".to_string(),
+            "Owner".to_string(),
+            "owner".to_string(),
+            "hash".to_string(),
+        );
+        storage.create_client(&client, &[], &[]).unwrap();
+        let event = Event::new(
+            client.id.clone(),
+            "Standup".to_string(),
+            String::new(),
+            start_date.to_string(),
+            start_time.to_string(),
+            start_date.to_string(),
+            String::new(),
+            "#336699".to_string(),
+        );
+        storage.create_event(&event).unwrap();
+        let starts_at = event.starts_at(Tz::UTC).unwrap();
+        storage.create_event_reminder(&EventReminder::new(&event, starts_at, minutes_before, "log".to_string())).unwrap();
+        event
+    }
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn due_reminder_is_sent_once() {
+        let storage = Storage::new(":memory:").unwrap();
+        seed_reminder(&storage, "2030-01-15", "09:00", 30);
+
+        assert_eq!(dispatch_due_reminders(&storage, at(2030, 1, 15, 8, 29), Tz::UTC).unwrap(), 0);
+        assert_eq!(dispatch_due_reminders(&storage, at(2030, 1, 15, 8, 30), Tz::UTC).unwrap(), 1);
+        assert_eq!(dispatch_due_reminders(&storage, at(2030, 1, 15, 8, 31), Tz::UTC).unwrap(), 0);
+        assert!(storage.get_pending_reminders().unwrap().is_empty());
+    }
+
+    #[test]
+    fn moved_event_reschedules_its_reminder() {
+        let storage = Storage::new(":memory:").unwrap();
+        let event = seed_reminder(&storage, "2030-01-15", "09:00", 30);
+        storage
+            .execute_raw(&format!("UPDATE events SET start_time = '11:00' WHERE id = '{}'", event.id))
+            .unwrap();
+
+        assert_eq!(dispatch_due_reminders(&storage, at(2030, 1, 15, 8, 30), Tz::UTC).unwrap(), 0);
+        let pending = storage.get_pending_reminders().unwrap();
+        assert_eq!(pending[0].reminder.fire_at, at(2030, 1, 15, 10, 30));
+        assert_eq!(dispatch_due_reminders(&storage, at(2030, 1, 15, 10, 30), Tz::UTC).unwrap(), 1);
+    }
+
+    #[test]
+    fn fire_time_follows_the_event_time_zone() {
+        let storage = Storage::new(":memory:").unwrap();
+        seed_reminder(&storage, "2030-01-15", "09:00", 0);
+        let new_york: Tz = "America/New_York".parse().unwrap();
+
+        assert_eq!(dispatch_due_reminders(&storage, at(2030, 1, 15, 9, 0), new_york).unwrap(), 0);
+        assert_eq!(dispatch_due_reminders(&storage, at(2030, 1, 15, 14, 0), new_york).unwrap(), 1);
+    }
+}
@@ -1,11 +1,13 @@
-use rusqlite::{params, Connection, Result, OpenFlags};
-use std::sync::{Arc, Mutex};
-use crate::models::{Client, Employee, Task, Event, DashboardStats};
+use rusqlite::{params, Connection, OptionalExtension, Result, OpenFlags, Row, ToSql};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use crate::models::{AppError, DEFAULT_TASK_PRIORITIES, TERMINATED_STATUS, Client, EmployeeQuery, Page, TaskQuery, TaskSort, TaskFacets, TagAction, Employee, Task, Event, EventAttendee, Document, EventReminder, PendingReminder, SessionData, RefreshTokenData, DashboardStats, PayrollBreakdown, PayrollGroup, PaymentEvent, SalaryChange, EmployeeDetail, ActivityItem, merge_activity, DataSummary, AccountDeletionImpact, SandboxSeed, completion_rate, PurgeOutcome, AttendeeOutcome, AssignOutcome, VacuumReport, DashboardTrends, TrendMetric};
 use std::path::Path;
-use chrono::{DateTime, Utc, NaiveDateTime};
+use chrono::{DateTime, Duration, Utc};
 
 pub struct Storage {
     conn: Arc<Mutex<Connection>>,
+    read_conn: Option<Arc<Mutex<Connection>>>,
     db_path: String,
 }
 
@@ -14,12 +16,33 @@ impl Storage {
         let conn = Connection::open(path)?;
         let storage = Self {
             conn: Arc::new(Mutex::new(conn)),
+            read_conn: None,
             db_path: path.to_string(),
         };
         storage.init_tables()?;
         Ok(storage)
     }
 
+    /// Opens a second, read-only connection to the same database file for list, get and
+    /// dashboard queries. The primary is switched to WAL so that reader sees every committed
+    /// write without waiting on the writer's lock.
+    pub fn with_read_replica(mut self) -> Result<Self> {
+        {
+            let conn = self.conn.lock().unwrap();
+            conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+        }
+        let read_conn = Connection::open_with_flags(
+            &self.db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX | OpenFlags::SQLITE_OPEN_URI,
+        )?;
+        self.read_conn = Some(Arc::new(Mutex::new(read_conn)));
+        Ok(self)
+    }
+
+    fn reader(&self) -> MutexGuard<'_, Connection> {
+        self.read_conn.as_ref().unwrap_or(&self.conn).lock().unwrap()
+    }
+
     fn init_tables(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         
@@ -35,7 +58,42 @@ impl Storage {
                 job_title TEXT,
                 username TEXT UNIQUE NOT NULL,
                 password_hash TEXT NOT NULL,
-                created_at TEXT NOT NULL
+                created_at TEXT NOT NULL,
+                custom_goal_text TEXT,
+                other_service_text TEXT,
+                last_login_at TEXT,
+                task_priorities TEXT,
+                deactivated_at TEXT
+            )",
+            [],
+        )?;
+
+        add_column_if_missing(&conn, "clients", "custom_goal_text", "TEXT")?;
+        add_column_if_missing(&conn, "clients", "other_service_text", "TEXT")?;
+        add_column_if_missing(&conn, "clients", "last_login_at", "TEXT")?;
+        add_column_if_missing(&conn, "clients", "task_priorities", "TEXT")?;
+        add_column_if_missing(&conn, "clients", "deactivated_at", "TEXT")?;
+        conn.execute(
+            "UPDATE clients SET task_priorities = ?1 WHERE task_priorities IS NULL",
+            params![DEFAULT_TASK_PRIORITIES.join(",")],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS client_services (
+                client_id TEXT NOT NULL,
+                service TEXT NOT NULL,
+                PRIMARY KEY(client_id, service),
+                FOREIGN KEY(client_id) REFERENCES clients(id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS client_platforms (
+                client_id TEXT NOT NULL,
+                platform TEXT NOT NULL,
+                PRIMARY KEY(client_id, platform),
+                FOREIGN KEY(client_id) REFERENCES clients(id)
             )",
             [],
         )?;
@@ -50,6 +108,8 @@ impl Storage {
                 status TEXT NOT NULL,
                 paid INTEGER NOT NULL,
                 created_at TEXT NOT NULL,
+                termination_date TEXT,
+                deleted_at TEXT,
                 FOREIGN KEY(client_id) REFERENCES clients(id)
             )",
             [],
@@ -63,11 +123,26 @@ impl Storage {
                 priority TEXT NOT NULL,
                 done INTEGER NOT NULL,
                 created_at TEXT NOT NULL,
+                completed_at TEXT,
+                position REAL NOT NULL DEFAULT 0,
+                assignee_id TEXT,
+                archived INTEGER NOT NULL DEFAULT 0,
+                deleted_at TEXT,
                 FOREIGN KEY(client_id) REFERENCES clients(id)
             )",
             [],
         )?;
 
+        add_column_if_missing(&conn, "employees", "termination_date", "TEXT")?;
+        add_column_if_missing(&conn, "tasks", "completed_at", "TEXT")?;
+        add_column_if_missing(&conn, "tasks", "position", "REAL NOT NULL DEFAULT 0")?;
+        add_column_if_missing(&conn, "tasks", "assignee_id", "TEXT")?;
+        add_column_if_missing(&conn, "employees", "deleted_at", "TEXT")?;
+        add_column_if_missing(&conn, "employees", "updated_at", "TEXT")?;
+        add_column_if_missing(&conn, "tasks", "deleted_at", "TEXT")?;
+        add_column_if_missing(&conn, "tasks", "archived", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(&conn, "tasks", "updated_at", "TEXT")?;
+
         conn.execute(
             "CREATE TABLE IF NOT EXISTS events (
                 id TEXT PRIMARY KEY,
@@ -80,11 +155,138 @@ impl Storage {
                 end_time TEXT,
                 color TEXT NOT NULL,
                 created_at TEXT NOT NULL,
+                deleted_at TEXT,
+                FOREIGN KEY(client_id) REFERENCES clients(id)
+            )",
+            [],
+        )?;
+
+        add_column_if_missing(&conn, "events", "deleted_at", "TEXT")?;
+        add_column_if_missing(&conn, "events", "updated_at", "TEXT")?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS task_tags (
+                task_id TEXT NOT NULL,
+                client_id TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY(task_id, tag),
+                FOREIGN KEY(task_id) REFERENCES tasks(id),
+                FOREIGN KEY(client_id) REFERENCES clients(id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS event_attendees (
+                event_id TEXT NOT NULL,
+                employee_id TEXT NOT NULL,
+                client_id TEXT NOT NULL,
+                PRIMARY KEY(event_id, employee_id),
+                FOREIGN KEY(event_id) REFERENCES events(id),
+                FOREIGN KEY(employee_id) REFERENCES employees(id),
+                FOREIGN KEY(client_id) REFERENCES clients(id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS event_reminders (
+                id TEXT PRIMARY KEY,
+                event_id TEXT NOT NULL,
+                client_id TEXT NOT NULL,
+                minutes_before INTEGER NOT NULL,
+                channel TEXT NOT NULL,
+                fire_at TEXT NOT NULL,
+                sent_at TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY(event_id) REFERENCES events(id),
+                FOREIGN KEY(client_id) REFERENCES clients(id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS documents (
+                id TEXT PRIMARY KEY,
+                client_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL,
+                file_type TEXT NOT NULL,
+                uploaded_at TEXT NOT NULL,
+                task_id TEXT,
+                event_id TEXT,
+                content BLOB,
+                FOREIGN KEY(client_id) REFERENCES clients(id),
+                FOREIGN KEY(task_id) REFERENCES tasks(id),
+                FOREIGN KEY(event_id) REFERENCES events(id)
+            )",
+            [],
+        )?;
+
+        add_column_if_missing(&conn, "documents", "task_id", "TEXT")?;
+        add_column_if_missing(&conn, "documents", "event_id", "TEXT")?;
+        add_column_if_missing(&conn, "documents", "content", "BLOB")?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                token TEXT PRIMARY KEY,
+                client_id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL,
+                refresh_family TEXT,
+                FOREIGN KEY(client_id) REFERENCES clients(id)
+            )",
+            [],
+        )?;
+
+        add_column_if_missing(&conn, "sessions", "refresh_family", "TEXT")?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS refresh_tokens (
+                token_hash TEXT PRIMARY KEY,
+                family_id TEXT NOT NULL,
+                client_id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL,
+                rotated_at TEXT,
+                revoked INTEGER NOT NULL DEFAULT 0,
                 FOREIGN KEY(client_id) REFERENCES clients(id)
             )",
             [],
         )?;
 
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS payment_events (
+                employee_id TEXT NOT NULL,
+                client_id TEXT NOT NULL,
+                paid INTEGER NOT NULL,
+                changed_at TEXT NOT NULL,
+                FOREIGN KEY(employee_id) REFERENCES employees(id),
+                FOREIGN KEY(client_id) REFERENCES clients(id)
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS salary_history (
+                employee_id TEXT NOT NULL,
+                client_id TEXT NOT NULL,
+                old_salary REAL NOT NULL,
+                new_salary REAL NOT NULL,
+                changed_at TEXT NOT NULL,
+                FOREIGN KEY(employee_id) REFERENCES employees(id),
+                FOREIGN KEY(client_id) REFERENCES clients(id)
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS password_history (
+                client_id TEXT NOT NULL,
+                password_hash TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY(client_id) REFERENCES clients(id)
+            )",
+            [],
+        )?;
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_employees_client ON employees(client_id)",
             [],
@@ -95,19 +297,78 @@ impl Storage {
             [],
         )?;
 
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_tasks_client_priority ON tasks(client_id, priority, position)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_tasks_assignee ON tasks(assignee_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_task_tags_client ON task_tags(client_id, tag)",
+            [],
+        )?;
+
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_events_client ON events(client_id)",
             [],
         )?;
 
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_event_attendees_employee ON event_attendees(employee_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_payment_events_employee ON payment_events(employee_id, changed_at)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_salary_history_employee ON salary_history(employee_id, changed_at)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_sessions_client ON sessions(client_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_event_reminders_due ON event_reminders(sent_at, fire_at)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_refresh_tokens_family ON refresh_tokens(family_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_documents_client ON documents(client_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_documents_task ON documents(task_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_documents_event ON documents(event_id)",
+            [],
+        )?;
+
         Ok(())
     }
 
-    pub fn create_client(&self, client: &Client) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT INTO clients (id, business_name, business_website, business_sector, revenue, goals, email, job_title, username, password_hash, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+    pub fn create_client(&self, client: &Client, services: &[String], platforms: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO clients (id, business_name, business_website, business_sector, revenue, goals, email, job_title, username, password_hash, created_at, custom_goal_text, other_service_text, task_priorities)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
             params![
                 client.id,
                 client.business_name,
@@ -120,278 +381,2252 @@ impl Storage {
                 client.username,
                 client.password_hash,
                 client.created_at.to_rfc3339(),
+                client.custom_goal_text,
+                client.other_service_text,
+                DEFAULT_TASK_PRIORITIES.join(","),
             ],
         )?;
-        Ok(())
+
+        for service in services.iter().map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            tx.execute(
+                "INSERT OR IGNORE INTO client_services (client_id, service) VALUES (?1, ?2)",
+                params![client.id, service],
+            )?;
+        }
+
+        for platform in platforms.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            tx.execute(
+                "INSERT OR IGNORE INTO client_platforms (client_id, platform) VALUES (?1, ?2)",
+                params![client.id, platform],
+            )?;
+        }
+
+        tx.commit()
     }
 
     pub fn get_client_by_username(&self, username: &str) -> Result<Option<Client>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare("SELECT * FROM clients WHERE username = ?1")?;
-        
-        let client_iter = stmt.query_map(params![username], |row| {
-            let created_str: String = row.get(10)?;
-            let created_at = DateTime::parse_from_rfc3339(&created_str)
-                .unwrap_or_else(|_| DateTime::parse_from_rfc3339("1970-01-01T00:00:00Z").unwrap())
-                .with_timezone(&Utc);
-
-            Ok(Client {
-                id: row.get(0)?,
-                business_name: row.get(1)?,
-                business_website: row.get(2)?,
-                business_sector: row.get(3)?,
-                revenue: row.get(4)?,
-                goals: row.get(5)?,
-                email: row.get(6)?,
-                job_title: row.get(7)?,
-                username: row.get(8)?,
-                password_hash: row.get(9)?,
-                created_at,
-            })
-        })?;
+        let mut client_iter = stmt.query_map(params![username], row_to_client)?;
 
-        for client in client_iter {
-            return Ok(Some(client?));
+        match client_iter.next() {
+            Some(client) => Ok(Some(client?)),
+            None => Ok(None),
         }
-        Ok(None)
-    }
-
-    pub fn create_employee(&self, employee: &Employee) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT INTO employees (id, client_id, name, title, salary, status, paid, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![
-                employee.id,
-                employee.client_id,
-                employee.name,
-                employee.title,
-                employee.salary,
-                employee.status,
-                if employee.paid { 1 } else { 0 },
-                employee.created_at.to_rfc3339(),
-            ],
-        )?;
-        Ok(())
     }
 
-    pub fn get_employees(&self, client_id: &str) -> Result<Vec<Employee>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT * FROM employees WHERE client_id = ?1 ORDER BY name ASC")?;
-        
-        let employee_iter = stmt.query_map(params![client_id], |row| {
-            let created_str: String = row.get(7)?;
-            let created_at = DateTime::parse_from_rfc3339(&created_str)
-                .unwrap_or_else(|_| DateTime::parse_from_rfc3339("1970-01-01T00:00:00Z").unwrap())
-                .with_timezone(&Utc);
-            
-            let paid_int: i32 = row.get(6)?;
-
-            Ok(Employee {
-                id: row.get(0)?,
-                client_id: row.get(1)?,
-                name: row.get(2)?,
-                title: row.get(3)?,
-                salary: row.get(4)?,
-                status: row.get(5)?,
-                paid: paid_int == 1,
-                created_at,
-            })
-        })?;
+    pub fn get_client_by_id(&self, id: &str) -> Result<Option<Client>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare("SELECT * FROM clients WHERE id = ?1")?;
+        let mut client_iter = stmt.query_map(params![id], row_to_client)?;
 
-        let mut employees = Vec::new();
-        for emp in employee_iter {
-            employees.push(emp?);
+        match client_iter.next() {
+            Some(client) => Ok(Some(client?)),
+            None => Ok(None),
         }
-        Ok(employees)
     }
 
-    pub fn delete_employee(&self, id: &str, client_id: &str) -> Result<usize> {
+    pub fn update_client_password(&self, id: &str, password_hash: &str) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM employees WHERE id = ?1 AND client_id = ?2", params![id, client_id])
+        conn.execute("UPDATE clients SET password_hash = ?1 WHERE id = ?2", params![password_hash, id])
     }
 
-    pub fn update_employee_paid_status(&self, id: &str, client_id: &str, paid: bool) -> Result<usize> {
-        let conn = self.conn.lock().unwrap();
-        let val = if paid { 1 } else { 0 };
-        conn.execute("UPDATE employees SET paid = ?1 WHERE id = ?2 AND client_id = ?3", params![val, id, client_id])
+    /// Stores the new hash and signs out every session except `keep_session` in one transaction,
+    /// so a failure can't leave the old sessions valid under the new password. Returns the number
+    /// of sessions revoked.
+    pub fn change_client_password(&self, id: &str, password_hash: &str, history_limit: u32, keep_session: Option<&str>) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("UPDATE clients SET password_hash = ?1 WHERE id = ?2", params![password_hash, id])?;
+        push_password_history(&tx, id, password_hash, history_limit)?;
+        let revoked = revoke_client_sessions(&tx, id, keep_session)?;
+        tx.commit()?;
+        Ok(revoked)
     }
 
-    pub fn create_task(&self, task: &Task) -> Result<()> {
+    pub fn record_password_hash(&self, client_id: &str, password_hash: &str, history_limit: u32) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT INTO tasks (id, client_id, title, priority, done, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                task.id,
-                task.client_id,
-                task.title,
-                task.priority,
-                if task.done { 1 } else { 0 },
-                task.created_at.to_rfc3339(),
-            ],
+        push_password_history(&conn, client_id, password_hash, history_limit)
+    }
+
+    pub fn get_password_history(&self, client_id: &str, limit: u32) -> Result<Vec<String>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            "SELECT password_hash FROM password_history WHERE client_id = ?1 ORDER BY created_at DESC, rowid DESC LIMIT ?2",
         )?;
-        Ok(())
+        let hashes = stmt.query_map(params![client_id, limit], |row| row.get(0))?.collect::<Result<Vec<String>>>()?;
+        Ok(hashes)
     }
 
-    pub fn get_tasks(&self, client_id: &str) -> Result<Vec<Task>> {
+    pub fn record_login(&self, id: &str, at: DateTime<Utc>) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT * FROM tasks WHERE client_id = ?1 ORDER BY created_at DESC")?;
-        
-        let task_iter = stmt.query_map(params![client_id], |row| {
-            let created_str: String = row.get(5)?;
-            let created_at = DateTime::parse_from_rfc3339(&created_str)
-                .unwrap_or_else(|_| DateTime::parse_from_rfc3339("1970-01-01T00:00:00Z").unwrap())
-                .with_timezone(&Utc);
-            
-            let done_int: i32 = row.get(4)?;
-
-            Ok(Task {
-                id: row.get(0)?,
-                client_id: row.get(1)?,
-                title: row.get(2)?,
-                priority: row.get(3)?,
-                done: done_int == 1,
-                created_at,
-            })
-        })?;
+        conn.execute("UPDATE clients SET last_login_at = ?1 WHERE id = ?2", params![at.to_rfc3339(), id])
+    }
 
-        let mut tasks = Vec::new();
-        for task in task_iter {
-            tasks.push(task?);
-        }
-        Ok(tasks)
+    pub fn deactivate_client(&self, id: &str, at: DateTime<Utc>) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("UPDATE clients SET deactivated_at = ?1 WHERE id = ?2", params![at.to_rfc3339(), id])?;
+        let revoked = revoke_client_sessions(&tx, id, None)?;
+        tx.commit()?;
+        Ok(revoked)
     }
 
-    pub fn update_task_status(&self, id: &str, client_id: &str, done: bool) -> Result<usize> {
+    pub fn is_client_deactivated(&self, id: &str) -> Result<bool> {
+        let conn = self.reader();
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM clients WHERE id = ?1 AND deactivated_at IS NOT NULL)",
+            params![id],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn reactivate_client(&self, id: &str) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
-        let val = if done { 1 } else { 0 };
-        conn.execute("UPDATE tasks SET done = ?1 WHERE id = ?2 AND client_id = ?3", params![val, id, client_id])
+        conn.execute("UPDATE clients SET deactivated_at = NULL WHERE id = ?1", params![id])
     }
 
-    pub fn delete_task(&self, id: &str, client_id: &str) -> Result<usize> {
+    pub fn get_task_priorities(&self, client_id: &str) -> Result<Vec<String>> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM tasks WHERE id = ?1 AND client_id = ?2", params![id, client_id])
+        load_task_priorities(&conn, client_id)
     }
 
-    pub fn create_event(&self, event: &Event) -> Result<()> {
+    pub fn set_task_priorities(&self, client_id: &str, priorities: &[String]) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO events (id, client_id, title, description, start_date, start_time, end_date, end_time, color, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            params![
-                event.id,
-                event.client_id,
-                event.title,
-                event.description,
-                event.start_date,
-                event.start_time,
-                event.end_date,
-                event.end_time,
-                event.color,
-                event.created_at.to_rfc3339(),
-            ],
-        )?;
-        Ok(())
+            "UPDATE clients SET task_priorities = ?1 WHERE id = ?2",
+            params![priorities.join(","), client_id],
+        )
+    }
+
+    pub fn get_client_services(&self, client_id: &str) -> Result<Vec<String>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare("SELECT service FROM client_services WHERE client_id = ?1 ORDER BY service ASC")?;
+        let rows = stmt.query_map(params![client_id], |row| row.get(0))?;
+        rows.collect()
     }
 
-    pub fn get_events(&self, client_id: &str) -> Result<Vec<Event>> {
+    pub fn get_client_platforms(&self, client_id: &str) -> Result<Vec<String>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare("SELECT platform FROM client_platforms WHERE client_id = ?1 ORDER BY platform ASC")?;
+        let rows = stmt.query_map(params![client_id], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Stores a freshly issued session together with the refresh token that starts its family.
+    /// Stores a new login and evicts the client's oldest session families beyond
+    /// `max_sessions`. Returns the number of families evicted.
+    pub fn create_login_session(&self, session: &SessionData, refresh: &RefreshTokenData, max_sessions: u32) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        insert_session(&tx, session)?;
+        insert_refresh_token(&tx, refresh)?;
+        let evicted = evict_excess_families(&tx, &session.client_id, max_sessions)?;
+        tx.commit()?;
+        Ok(evicted)
+    }
+
+    pub fn get_session(&self, token: &str) -> Result<Option<SessionData>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT * FROM events WHERE client_id = ?1 ORDER BY start_date ASC")?;
-        
-        let event_iter = stmt.query_map(params![client_id], |row| {
-            let created_str: String = row.get(9)?;
-            let created_at = DateTime::parse_from_rfc3339(&created_str)
-                .unwrap_or_else(|_| DateTime::parse_from_rfc3339("1970-01-01T00:00:00Z").unwrap())
-                .with_timezone(&Utc);
-
-            Ok(Event {
-                id: row.get(0)?,
+        let mut stmt = conn.prepare("SELECT token, client_id, created_at, expires_at, refresh_family FROM sessions WHERE token = ?1")?;
+        let mut session_iter = stmt.query_map(params![token], |row| {
+            let created_str: String = row.get(2)?;
+            let expires_str: String = row.get(3)?;
+
+            Ok(SessionData {
+                token: row.get(0)?,
                 client_id: row.get(1)?,
-                title: row.get(2)?,
-                description: row.get(3)?,
-                start_date: row.get(4)?,
-                start_time: row.get(5)?,
-                end_date: row.get(6)?,
-                end_time: row.get(7)?,
-                color: row.get(8)?,
-                created_at,
+                created_at: parse_timestamp(&created_str),
+                expires_at: parse_timestamp(&expires_str),
+                refresh_family: row.get(4)?,
             })
         })?;
 
-        let mut events = Vec::new();
-        for ev in event_iter {
-            events.push(ev?);
+        match session_iter.next() {
+            Some(session) => Ok(Some(session?)),
+            None => Ok(None),
         }
-        Ok(events)
     }
 
-    pub fn delete_event(&self, id: &str, client_id: &str) -> Result<usize> {
+    pub fn extend_session(&self, token: &str, expires_at: DateTime<Utc>) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM events WHERE id = ?1 AND client_id = ?2", params![id, client_id])
+        conn.execute("UPDATE sessions SET expires_at = ?1 WHERE token = ?2", params![expires_at.to_rfc3339(), token])
     }
 
-    pub fn get_dashboard_stats(&self, client_id: &str) -> Result<DashboardStats> {
+    pub fn delete_session(&self, token: &str) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
-        
-        let emp_count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM employees WHERE client_id = ?",
-            params![client_id],
-            |row| row.get(0),
-        )?;
+        conn.execute("DELETE FROM sessions WHERE token = ?1", params![token])
+    }
 
-        let salary_total: f64 = conn.query_row(
-            "SELECT COALESCE(SUM(salary), 0.0) FROM employees WHERE client_id = ?",
-            params![client_id],
-            |row| row.get(0),
+    pub fn get_refresh_token(&self, token_hash: &str) -> Result<Option<RefreshTokenData>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT token_hash, family_id, client_id, created_at, expires_at, rotated_at, revoked FROM refresh_tokens WHERE token_hash = ?1",
         )?;
+        let mut token_iter = stmt.query_map(params![token_hash], |row| {
+            let created_str: String = row.get(3)?;
+            let expires_str: String = row.get(4)?;
+            let rotated_str: Option<String> = row.get(5)?;
 
-        let active_tasks: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM tasks WHERE client_id = ? AND done = 0",
-            params![client_id],
-            |row| row.get(0),
-        )?;
+            Ok(RefreshTokenData {
+                token_hash: row.get(0)?,
+                family_id: row.get(1)?,
+                client_id: row.get(2)?,
+                created_at: parse_timestamp(&created_str),
+                expires_at: parse_timestamp(&expires_str),
+                rotated_at: rotated_str.as_deref().map(parse_timestamp),
+                revoked: row.get(6)?,
+            })
+        })?;
 
-        let event_count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM events WHERE client_id = ?",
-            params![client_id],
-            |row| row.get(0),
+        match token_iter.next() {
+            Some(token) => Ok(Some(token?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Marks `old_hash` as rotated and stores its replacement along with the new session, which
+    /// supersedes any session the family already had. The session cap is enforced here too.
+    /// Returns false without writing anything if the old token was already rotated or revoked.
+    pub fn rotate_refresh_token(&self, old_hash: &str, next: &RefreshTokenData, session: &SessionData, max_sessions: u32) -> Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let rotated = tx.execute(
+            "UPDATE refresh_tokens SET rotated_at = ?1 WHERE token_hash = ?2 AND rotated_at IS NULL AND revoked = 0",
+            params![next.created_at.to_rfc3339(), old_hash],
         )?;
+        if rotated == 0 {
+            return Ok(false);
+        }
 
-        Ok(DashboardStats {
-            total_employees: emp_count,
-            monthly_payroll: salary_total,
-            active_tasks,
-            total_events: event_count,
-        })
+        insert_refresh_token(&tx, next)?;
+        tx.execute("DELETE FROM sessions WHERE refresh_family = ?1", params![next.family_id])?;
+        insert_session(&tx, session)?;
+        evict_excess_families(&tx, &session.client_id, max_sessions)?;
+        tx.commit()?;
+        Ok(true)
     }
 
-    pub fn check_health(&self) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
-        let result: i32 = conn.query_row("SELECT 1", [], |r| r.get(0))?;
-        Ok(result == 1)
+    pub fn revoke_refresh_family(&self, family_id: &str) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let revoked = tx.execute("UPDATE refresh_tokens SET revoked = 1 WHERE family_id = ?1", params![family_id])?;
+        tx.execute("DELETE FROM sessions WHERE refresh_family = ?1", params![family_id])?;
+        tx.commit()?;
+        Ok(revoked)
     }
 
-    pub fn backup_db(&self, backup_path: &str) -> Result<()> {
+    pub fn create_employee(&self, employee: &Employee) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        insert_employee(&conn, employee)
+    }
+
+    pub fn create_employees(&self, employees: &[Employee]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for employee in employees {
+            insert_employee(&tx, employee)?;
+        }
+        tx.commit()?;
+        Ok(employees.len())
+    }
+
+    pub fn get_employees(&self, client_id: &str, query: &EmployeeQuery, page: &Page) -> Result<Vec<Employee>> {
+        let conn = self.reader();
+        let mut sql = String::from("SELECT * FROM employees WHERE client_id = ? AND deleted_at IS NULL");
+        let mut values: Vec<&dyn ToSql> = vec![&client_id];
+
+        if let Some(min) = &query.min_salary {
+            sql.push_str(" AND salary >= ?");
+            values.push(min);
+        }
+        if let Some(max) = &query.max_salary {
+            sql.push_str(" AND salary <= ?");
+            values.push(max);
+        }
+        sql.push_str(" ORDER BY name ASC LIMIT ? OFFSET ?");
+        values.push(&page.limit);
+        values.push(&page.offset);
+
+        let mut stmt = conn.prepare(&sql)?;
+        let employee_iter = stmt.query_map(values.as_slice(), row_to_employee)?;
+
+        let mut employees = Vec::new();
+        for emp in employee_iter {
+            employees.push(emp?);
+        }
+        Ok(employees)
+    }
+
+    pub fn get_unassigned_employees(&self, client_id: &str) -> Result<Vec<Employee>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            "SELECT e.* FROM employees e
+             LEFT JOIN tasks t ON t.assignee_id = e.id AND t.client_id = e.client_id AND t.deleted_at IS NULL
+             WHERE e.client_id = ?1 AND e.status != ?2 AND e.deleted_at IS NULL AND t.id IS NULL
+             ORDER BY e.name ASC",
+        )?;
+        let employee_iter = stmt.query_map(params![client_id, TERMINATED_STATUS], row_to_employee)?;
+
+        let mut employees = Vec::new();
+        for emp in employee_iter {
+            employees.push(emp?);
+        }
+        Ok(employees)
+    }
+
+    pub fn get_employee(&self, id: &str, client_id: &str) -> Result<Option<Employee>> {
+        let conn = self.reader();
+        load_employee(&conn, id, client_id)
+    }
+
+    pub fn get_employee_detail(&self, id: &str, client_id: &str) -> Result<Option<EmployeeDetail>> {
+        // One read transaction, so the bundle can't mix state from before and after a write.
+        let mut conn = self.reader();
+        let tx = conn.transaction()?;
+        let employee = match load_employee(&tx, id, client_id)? {
+            Some(employee) => employee,
+            None => return Ok(None),
+        };
+
+        let detail = EmployeeDetail {
+            employee,
+            tasks: load_employee_tasks(&tx, id, client_id)?,
+            events: load_employee_events(&tx, id, client_id)?,
+            salary_history: load_salary_history(&tx, id, client_id)?,
+            payment_history: load_payment_events(&tx, id, client_id)?,
+        };
+        tx.commit()?;
+        Ok(Some(detail))
+    }
+
+    pub fn employee_exists(&self, id: &str, client_id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM employees WHERE id = ?1 AND client_id = ?2 AND deleted_at IS NULL",
+            params![id, client_id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    pub fn delete_employee(&self, id: &str, client_id: &str) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM event_attendees WHERE employee_id = ?1 AND client_id = ?2", params![id, client_id])?;
+        let now = Utc::now().to_rfc3339();
+        tx.execute(
+            "UPDATE tasks SET assignee_id = NULL, updated_at = ?1 WHERE assignee_id = ?2 AND client_id = ?3",
+            params![now, id, client_id],
+        )?;
+        let deleted = tx.execute(
+            "UPDATE employees SET deleted_at = ?1 WHERE id = ?2 AND client_id = ?3 AND deleted_at IS NULL",
+            params![now, id, client_id],
+        )?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    pub fn purge_employee(&self, id: &str, client_id: &str) -> Result<PurgeOutcome> {
+        self.purge("employees", id, client_id, &[
+            "DELETE FROM event_attendees WHERE employee_id = ?1 AND client_id = ?2",
+            "UPDATE tasks SET assignee_id = NULL WHERE assignee_id = ?1 AND client_id = ?2",
+            "DELETE FROM payment_events WHERE employee_id = ?1 AND client_id = ?2",
+            "DELETE FROM salary_history WHERE employee_id = ?1 AND client_id = ?2",
+        ])
+    }
+
+    pub fn update_employee_paid_status(&self, id: &str, client_id: &str, paid: bool) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let current: Option<bool> = tx
+            .query_row(
+                "SELECT paid FROM employees WHERE id = ?1 AND client_id = ?2 AND deleted_at IS NULL",
+                params![id, client_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match current {
+            None => return Ok(0),
+            Some(current) if current == paid => return Ok(1),
+            Some(_) => {},
+        }
+
+        let changed_at = Utc::now().to_rfc3339();
+        tx.execute(
+            "UPDATE employees SET paid = ?1, updated_at = ?2 WHERE id = ?3 AND client_id = ?4",
+            params![paid, changed_at, id, client_id],
+        )?;
+        tx.execute(
+            "INSERT INTO payment_events (employee_id, client_id, paid, changed_at) VALUES (?1, ?2, ?3, ?4)",
+            params![id, client_id, paid, changed_at],
+        )?;
+        tx.commit()?;
+        Ok(1)
+    }
+
+    pub fn update_employee_salary(&self, id: &str, client_id: &str, salary: f64) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let current: Option<f64> = tx
+            .query_row(
+                "SELECT salary FROM employees WHERE id = ?1 AND client_id = ?2 AND deleted_at IS NULL",
+                params![id, client_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let old_salary = match current {
+            None => return Ok(0),
+            Some(current) if current == salary => return Ok(1),
+            Some(current) => current,
+        };
+
+        let changed_at = Utc::now().to_rfc3339();
+        tx.execute(
+            "UPDATE employees SET salary = ?1, updated_at = ?2 WHERE id = ?3 AND client_id = ?4",
+            params![salary, changed_at, id, client_id],
+        )?;
+        tx.execute(
+            "INSERT INTO salary_history (employee_id, client_id, old_salary, new_salary, changed_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, client_id, old_salary, salary, changed_at],
+        )?;
+        tx.commit()?;
+        Ok(1)
+    }
+
+    pub fn get_payment_events(&self, employee_id: &str, client_id: &str) -> Result<Vec<PaymentEvent>> {
+        let conn = self.reader();
+        load_payment_events(&conn, employee_id, client_id)
+    }
+
+    pub fn terminate_employee(&self, id: &str, client_id: &str, termination_date: &str) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE employees SET status = ?1, termination_date = ?2, updated_at = ?3 WHERE id = ?4 AND client_id = ?5 AND deleted_at IS NULL",
+            params![TERMINATED_STATUS, termination_date, Utc::now().to_rfc3339(), id, client_id],
+        )
+    }
+
+    pub fn bulk_update_employee_paid_status(&self, ids: &[String], client_id: &str, paid: bool) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let val = if paid { 1 } else { 0 };
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let changed_at = Utc::now().to_rfc3339();
+
+        let history_sql = format!(
+            "INSERT INTO payment_events (employee_id, client_id, paid, changed_at)
+             SELECT id, client_id, ?, ? FROM employees WHERE client_id = ? AND deleted_at IS NULL AND paid != ? AND id IN ({})",
+            placeholders
+        );
+        let mut values: Vec<&dyn ToSql> = vec![&val, &changed_at, &client_id, &val];
+        values.extend(ids.iter().map(|id| id as &dyn ToSql));
+        tx.execute(&history_sql, values.as_slice())?;
+
+        let sql = format!(
+            "UPDATE employees SET updated_at = CASE WHEN paid != ? THEN ? ELSE updated_at END, paid = ?
+             WHERE client_id = ? AND deleted_at IS NULL AND id IN ({})",
+            placeholders
+        );
+        let mut values: Vec<&dyn ToSql> = vec![&val, &changed_at, &val, &client_id];
+        values.extend(ids.iter().map(|id| id as &dyn ToSql));
+
+        let updated = tx.execute(&sql, values.as_slice())?;
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    pub fn count_owned_tasks(&self, ids: &[String], client_id: &str) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let sql = format!("SELECT COUNT(*) FROM tasks WHERE client_id = ? AND deleted_at IS NULL AND id IN ({})", placeholders);
+
+        let mut values: Vec<&dyn ToSql> = vec![&client_id];
+        values.extend(ids.iter().map(|id| id as &dyn ToSql));
+
+        let count: i64 = conn.query_row(&sql, values.as_slice(), |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    pub fn count_tasks_at_tag_limit(&self, ids: &[String], client_id: &str, tag: &str, limit: usize) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let sql = format!(
+            "SELECT COUNT(*) FROM (
+                SELECT task_id FROM task_tags
+                WHERE client_id = ? AND task_id IN ({})
+                GROUP BY task_id
+                HAVING COUNT(*) >= ? AND SUM(tag = ?) = 0
+            )",
+            placeholders
+        );
+
+        let limit = limit as i64;
+        let mut values: Vec<&dyn ToSql> = vec![&client_id];
+        values.extend(ids.iter().map(|id| id as &dyn ToSql));
+        values.push(&limit);
+        values.push(&tag);
+
+        let count: i64 = conn.query_row(&sql, values.as_slice(), |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    pub fn bulk_tag_tasks(&self, ids: &[String], client_id: &str, tag: &str, action: TagAction) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut affected = 0;
+        for id in ids {
+            let changed = match action {
+                TagAction::Add => tx.execute(
+                    "INSERT OR IGNORE INTO task_tags (task_id, client_id, tag) VALUES (?1, ?2, ?3)",
+                    params![id, client_id, tag],
+                )?,
+                TagAction::Remove => tx.execute(
+                    "DELETE FROM task_tags WHERE task_id = ?1 AND client_id = ?2 AND tag = ?3",
+                    params![id, client_id, tag],
+                )?,
+            };
+            if changed > 0 {
+                touch_task(&tx, id, client_id)?;
+            }
+            affected += changed;
+        }
+        tx.commit()?;
+        Ok(affected)
+    }
+
+    pub fn next_task_position(&self, client_id: &str, priority: &str) -> Result<f64> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT COALESCE(MAX(position), 0.0) + 1.0 FROM tasks WHERE client_id = ?1 AND priority = ?2 AND deleted_at IS NULL",
+            params![client_id, priority],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn create_task(&self, task: &Task) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        insert_task(&tx, task)?;
+        tx.commit()
+    }
+
+    pub fn get_tasks(&self, client_id: &str, query: &TaskQuery, page: &Page) -> Result<Vec<Task>> {
+        let conn = self.reader();
+        let created_since = query.created_since(Utc::now()).map(|t| t.to_rfc3339());
+        let mut sql = String::from("SELECT * FROM tasks WHERE client_id = ? AND deleted_at IS NULL");
+        let mut values: Vec<&dyn ToSql> = vec![&client_id];
+
+        if let Some(since) = &created_since {
+            sql.push_str(" AND created_at >= ?");
+            values.push(since);
+        }
+        if let Some(done) = &query.done {
+            sql.push_str(" AND done = ?");
+            values.push(done);
+        }
+        if let Some(archived) = &query.archived {
+            sql.push_str(" AND archived = ?");
+            values.push(archived);
+        }
+        if let Some(priority) = &query.priority {
+            sql.push_str(" AND priority = ? COLLATE NOCASE");
+            values.push(priority);
+        }
+        if let Some(tag) = &query.tag {
+            sql.push_str(" AND id IN (SELECT task_id FROM task_tags WHERE client_id = ? AND tag = ?)");
+            values.push(&client_id);
+            values.push(tag);
+        }
+        let priorities = match query.sort {
+            TaskSort::Newest => Vec::new(),
+            TaskSort::Board => load_task_priorities(&conn, client_id)?,
+        };
+        match query.sort {
+            TaskSort::Newest => sql.push_str(" ORDER BY created_at DESC"),
+            TaskSort::Board => {
+                sql.push_str(&format!(" ORDER BY {}, position ASC, created_at DESC", priority_rank_sql(&priorities)));
+                values.extend(priorities.iter().map(|p| p as &dyn ToSql));
+            },
+        }
+        sql.push_str(" LIMIT ? OFFSET ?");
+        values.push(&page.limit);
+        values.push(&page.offset);
+
+        let mut stmt = conn.prepare(&sql)?;
+        let task_iter = stmt.query_map(values.as_slice(), row_to_task)?;
+
+        let mut tasks = Vec::new();
+        for task in task_iter {
+            tasks.push(task?);
+        }
+        load_task_tags(&conn, client_id, &mut tasks)?;
+        Ok(tasks)
+    }
+
+    pub fn get_task_facets(&self, client_id: &str) -> Result<TaskFacets> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare("SELECT DISTINCT priority FROM tasks WHERE client_id = ?1 AND deleted_at IS NULL ORDER BY priority ASC")?;
+        let priorities = stmt.query_map(params![client_id], |row| row.get(0))?.collect::<Result<Vec<String>>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT tag FROM task_tags
+             WHERE client_id = ?1 AND task_id IN (SELECT id FROM tasks WHERE client_id = ?1 AND deleted_at IS NULL)
+             ORDER BY tag ASC",
+        )?;
+        let tags = stmt.query_map(params![client_id], |row| row.get(0))?.collect::<Result<Vec<String>>>()?;
+
+        Ok(TaskFacets { priorities, tags })
+    }
+
+    pub fn update_task_status(&self, id: &str, client_id: &str, done: bool) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let val = if done { 1 } else { 0 };
+        let now = Utc::now().to_rfc3339();
+        let completed_at = if done { Some(now.as_str()) } else { None };
+        conn.execute(
+            "UPDATE tasks SET done = ?1,
+                completed_at = CASE WHEN done = ?1 THEN completed_at ELSE ?2 END,
+                updated_at = CASE WHEN done = ?1 THEN updated_at ELSE ?3 END
+             WHERE id = ?4 AND client_id = ?5 AND deleted_at IS NULL",
+            params![val, completed_at, now, id, client_id],
+        )
+    }
+
+    pub fn set_task_archived(&self, id: &str, client_id: &str, archived: bool) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE tasks SET archived = ?1, updated_at = ?2 WHERE id = ?3 AND client_id = ?4 AND deleted_at IS NULL",
+            params![archived, Utc::now().to_rfc3339(), id, client_id],
+        )
+    }
+
+    /// Sets or clears a task's assignee. The assignee is checked in the same transaction as the
+    /// update so it can't be deleted in between.
+    pub fn set_task_assignee(&self, id: &str, client_id: &str, assignee_id: Option<&str>) -> Result<AssignOutcome> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let exists = |table: &str, id: &str| -> Result<bool> {
+            tx.query_row(
+                &format!("SELECT EXISTS(SELECT 1 FROM {} WHERE id = ?1 AND client_id = ?2 AND deleted_at IS NULL)", table),
+                params![id, client_id],
+                |row| row.get(0),
+            )
+        };
+
+        if !exists("tasks", id)? {
+            return Ok(AssignOutcome::TaskNotFound);
+        }
+        if let Some(assignee_id) = assignee_id {
+            if !exists("employees", assignee_id)? {
+                return Ok(AssignOutcome::AssigneeNotFound);
+            }
+        }
+
+        tx.execute(
+            "UPDATE tasks SET assignee_id = ?1, updated_at = ?2 WHERE id = ?3 AND client_id = ?4 AND deleted_at IS NULL",
+            params![assignee_id, Utc::now().to_rfc3339(), id, client_id],
+        )?;
+        tx.commit()?;
+        Ok(AssignOutcome::Updated)
+    }
+
+    pub fn delete_archived_completed_tasks(&self, client_id: &str) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM task_tags WHERE client_id = ?1
+             AND task_id IN (SELECT id FROM tasks WHERE client_id = ?1 AND done = 1 AND archived = 1 AND deleted_at IS NULL)",
+            params![client_id],
+        )?;
+        tx.execute(
+            "DELETE FROM documents WHERE client_id = ?1
+             AND task_id IN (SELECT id FROM tasks WHERE client_id = ?1 AND done = 1 AND archived = 1 AND deleted_at IS NULL)",
+            params![client_id],
+        )?;
+        let deleted = tx.execute("DELETE FROM tasks WHERE client_id = ?1 AND done = 1 AND archived = 1 AND deleted_at IS NULL", params![client_id])?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    pub fn reorder_task(&self, id: &str, client_id: &str, priority: &str, position: f64) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE tasks SET priority = ?1, position = ?2, updated_at = ?3 WHERE id = ?4 AND client_id = ?5 AND deleted_at IS NULL",
+            params![priority, position, Utc::now().to_rfc3339(), id, client_id],
+        )
+    }
+
+    pub fn delete_task(&self, id: &str, client_id: &str) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE tasks SET deleted_at = ?1 WHERE id = ?2 AND client_id = ?3 AND deleted_at IS NULL",
+            params![Utc::now().to_rfc3339(), id, client_id],
+        )
+    }
+
+    pub fn purge_task(&self, id: &str, client_id: &str) -> Result<PurgeOutcome> {
+        self.purge("tasks", id, client_id, &[
+            "DELETE FROM task_tags WHERE task_id = ?1 AND client_id = ?2",
+            "DELETE FROM documents WHERE task_id = ?1 AND client_id = ?2",
+        ])
+    }
+
+    pub fn create_event(&self, event: &Event) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        insert_event(&conn, event)
+    }
+
+    pub fn create_events(&self, events: &[Event]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for event in events {
+            insert_event(&tx, event)?;
+        }
+        tx.commit()
+    }
+
+    pub fn get_events(&self, client_id: &str, page: &Page) -> Result<Vec<Event>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            "SELECT * FROM events WHERE client_id = ?1 AND deleted_at IS NULL ORDER BY start_date ASC LIMIT ?2 OFFSET ?3",
+        )?;
+        let event_iter = stmt.query_map(params![client_id, page.limit, page.offset], row_to_event)?;
+
+        let mut events = Vec::new();
+        for ev in event_iter {
+            events.push(ev?);
+        }
+        load_attendees(&conn, client_id, &mut events)?;
+        Ok(events)
+    }
+
+    pub fn get_event(&self, id: &str, client_id: &str) -> Result<Option<Event>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare("SELECT * FROM events WHERE id = ?1 AND client_id = ?2 AND deleted_at IS NULL")?;
+        let mut events = stmt.query_map(params![id, client_id], row_to_event)?.collect::<Result<Vec<_>>>()?;
+        load_attendees(&conn, client_id, &mut events)?;
+        Ok(events.pop())
+    }
+
+    pub fn create_event_reminder(&self, reminder: &EventReminder) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO event_reminders (id, event_id, client_id, minutes_before, channel, fire_at, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                reminder.id,
+                reminder.event_id,
+                reminder.client_id,
+                reminder.minutes_before,
+                reminder.channel,
+                reminder.fire_at.to_rfc3339(),
+                reminder.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_pending_reminders(&self) -> Result<Vec<PendingReminder>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT r.*, e.title AS event_title, e.start_date AS event_start_date, e.start_time AS event_start_time
+             FROM event_reminders r
+             JOIN events e ON e.id = r.event_id AND e.client_id = r.client_id AND e.deleted_at IS NULL
+             WHERE r.sent_at IS NULL
+             ORDER BY r.fire_at ASC",
+        )?;
+        let reminder_iter = stmt.query_map([], |row| {
+            Ok(PendingReminder {
+                reminder: row_to_reminder(row)?,
+                event_title: row.get("event_title")?,
+                event_start_date: row.get("event_start_date")?,
+                event_start_time: row.get("event_start_time")?,
+            })
+        })?;
+
+        let mut reminders = Vec::new();
+        for reminder in reminder_iter {
+            reminders.push(reminder?);
+        }
+        Ok(reminders)
+    }
+
+    pub fn reschedule_reminder(&self, id: &str, fire_at: DateTime<Utc>) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE event_reminders SET fire_at = ?1 WHERE id = ?2 AND sent_at IS NULL",
+            params![fire_at.to_rfc3339(), id],
+        )
+    }
+
+    pub fn mark_reminder_sent(&self, id: &str, sent_at: DateTime<Utc>) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE event_reminders SET sent_at = ?1 WHERE id = ?2 AND sent_at IS NULL",
+            params![sent_at.to_rfc3339(), id],
+        )
+    }
+
+    pub fn task_exists(&self, id: &str, client_id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM tasks WHERE id = ?1 AND client_id = ?2 AND deleted_at IS NULL",
+            params![id, client_id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    pub fn event_exists(&self, id: &str, client_id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM events WHERE id = ?1 AND client_id = ?2 AND deleted_at IS NULL",
+            params![id, client_id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    pub fn delete_event(&self, id: &str, client_id: &str) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM event_attendees WHERE event_id = ?1 AND client_id = ?2", params![id, client_id])?;
+        tx.execute("DELETE FROM event_reminders WHERE event_id = ?1 AND client_id = ?2", params![id, client_id])?;
+        let deleted = tx.execute(
+            "UPDATE events SET deleted_at = ?1 WHERE id = ?2 AND client_id = ?3 AND deleted_at IS NULL",
+            params![Utc::now().to_rfc3339(), id, client_id],
+        )?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    pub fn purge_event(&self, id: &str, client_id: &str) -> Result<PurgeOutcome> {
+        self.purge("events", id, client_id, &[
+            "DELETE FROM event_attendees WHERE event_id = ?1 AND client_id = ?2",
+            "DELETE FROM event_reminders WHERE event_id = ?1 AND client_id = ?2",
+            "DELETE FROM documents WHERE event_id = ?1 AND client_id = ?2",
+        ])
+    }
+
+    /// Hard-deletes a row from `table` only if it has already been soft-deleted, running
+    /// `cleanup` (bound to the row id and client id) first to remove anything referencing it.
+    fn purge(&self, table: &str, id: &str, client_id: &str, cleanup: &[&str]) -> Result<PurgeOutcome> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let deleted_at: Option<Option<String>> = tx
+            .query_row(
+                &format!("SELECT deleted_at FROM {} WHERE id = ?1 AND client_id = ?2", table),
+                params![id, client_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match deleted_at {
+            None => return Ok(PurgeOutcome::NotFound),
+            Some(None) => return Ok(PurgeOutcome::StillActive),
+            Some(Some(_)) => {},
+        }
+
+        for sql in cleanup {
+            tx.execute(sql, params![id, client_id])?;
+        }
+        tx.execute(&format!("DELETE FROM {} WHERE id = ?1 AND client_id = ?2", table), params![id, client_id])?;
+        tx.commit()?;
+        Ok(PurgeOutcome::Purged)
+    }
+
+    /// Checks the event and every employee inside the insert transaction, so a concurrent
+    /// delete cannot slip in between the check and the insert.
+    pub fn add_event_attendees(&self, event_id: &str, client_id: &str, employee_ids: &[String]) -> Result<AttendeeOutcome> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let exists = |table: &str, id: &str| -> Result<bool> {
+            tx.query_row(
+                &format!("SELECT EXISTS(SELECT 1 FROM {} WHERE id = ?1 AND client_id = ?2 AND deleted_at IS NULL)", table),
+                params![id, client_id],
+                |row| row.get(0),
+            )
+        };
+
+        if !exists("events", event_id)? {
+            return Ok(AttendeeOutcome::EventNotFound);
+        }
+        for employee_id in employee_ids {
+            if !exists("employees", employee_id)? {
+                return Ok(AttendeeOutcome::EmployeeNotFound(employee_id.clone()));
+            }
+        }
+
+        let mut added = 0;
+        for employee_id in employee_ids {
+            added += tx.execute(
+                "INSERT OR IGNORE INTO event_attendees (event_id, employee_id, client_id) VALUES (?1, ?2, ?3)",
+                params![event_id, employee_id, client_id],
+            )?;
+        }
+        if added > 0 {
+            touch_event(&tx, event_id, client_id)?;
+        }
+        tx.commit()?;
+        Ok(AttendeeOutcome::Added(added))
+    }
+
+    pub fn remove_event_attendee(&self, event_id: &str, employee_id: &str, client_id: &str) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let removed = tx.execute(
+            "DELETE FROM event_attendees WHERE event_id = ?1 AND employee_id = ?2 AND client_id = ?3",
+            params![event_id, employee_id, client_id],
+        )?;
+        if removed > 0 {
+            touch_event(&tx, event_id, client_id)?;
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    pub fn get_employee_events(&self, employee_id: &str, client_id: &str) -> Result<Vec<Event>> {
+        let conn = self.reader();
+        load_employee_events(&conn, employee_id, client_id)
+    }
+
+    pub fn get_dashboard_stats(&self, client_id: &str) -> Result<DashboardStats> {
+        let conn = self.reader();
+        
+        let emp_count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM employees WHERE client_id = ?1 AND status != ?2 AND deleted_at IS NULL",
+            params![client_id, TERMINATED_STATUS],
+            |row| row.get(0),
+        )?;
+
+        let salary_total: f64 = conn.query_row(
+            "SELECT COALESCE(SUM(salary), 0.0) FROM employees WHERE client_id = ?1 AND status != ?2 AND deleted_at IS NULL",
+            params![client_id, TERMINATED_STATUS],
+            |row| row.get(0),
+        )?;
+
+        let (total_tasks, active_tasks): (i64, i64) = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(done = 0), 0) FROM tasks WHERE client_id = ? AND deleted_at IS NULL",
+            params![client_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let event_count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM events WHERE client_id = ? AND deleted_at IS NULL",
+            params![client_id],
+            |row| row.get(0),
+        )?;
+
+        Ok(DashboardStats {
+            total_employees: emp_count,
+            monthly_payroll: salary_total,
+            active_tasks,
+            tasks_completion_rate: completion_rate(total_tasks - active_tasks, total_tasks),
+            total_events: event_count,
+        })
+    }
+
+    pub fn get_payroll_breakdown(&self, client_id: &str) -> Result<PayrollBreakdown> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            // Statuses are free text, so "Active" and " active" are one group. MIN picks the
+            // capitalised spelling when both are present.
+            "SELECT MIN(TRIM(status)), COUNT(*), COALESCE(SUM(salary), 0.0) FROM employees
+             WHERE client_id = ?1 AND deleted_at IS NULL
+             GROUP BY LOWER(TRIM(status))
+             ORDER BY LOWER(TRIM(status)) ASC",
+        )?;
+        let groups = stmt
+            .query_map(params![client_id], |row| {
+                Ok(PayrollGroup {
+                    status: row.get(0)?,
+                    employees: row.get(1)?,
+                    salary_total: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let salary_total = groups.iter().map(|g| g.salary_total).sum();
+        Ok(PayrollBreakdown { groups, salary_total })
+    }
+
+    pub fn get_activity(&self, client_id: &str, page: &Page) -> Result<Vec<ActivityItem>> {
+        let conn = self.reader();
+        let fetch = if page.limit < 0 { -1 } else { page.offset + page.limit };
+        let mut items = Vec::new();
+
+        let mut stmt = conn.prepare(
+            "SELECT * FROM employees WHERE client_id = ?1 AND deleted_at IS NULL ORDER BY COALESCE(updated_at, created_at) DESC LIMIT ?2",
+        )?;
+        for employee in stmt.query_map(params![client_id, fetch], row_to_employee)? {
+            items.push(ActivityItem::from(&employee?));
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT * FROM tasks WHERE client_id = ?1 AND deleted_at IS NULL ORDER BY COALESCE(updated_at, completed_at, created_at) DESC LIMIT ?2",
+        )?;
+        for task in stmt.query_map(params![client_id, fetch], row_to_task)? {
+            items.push(ActivityItem::from(&task?));
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT * FROM events WHERE client_id = ?1 AND deleted_at IS NULL ORDER BY COALESCE(updated_at, created_at) DESC LIMIT ?2",
+        )?;
+        for event in stmt.query_map(params![client_id, fetch], row_to_event)? {
+            items.push(ActivityItem::from(&event?));
+        }
+
+        Ok(merge_activity(items, page))
+    }
+
+    pub fn get_dashboard_trends(&self, client_id: &str, now: DateTime<Utc>, period: Duration) -> Result<DashboardTrends> {
+        let conn = self.reader();
+        let current_start = now - period;
+        let previous_start = current_start - period;
+
+        let headcount_at = |end: &DateTime<Utc>| -> Result<(i64, f64)> {
+            conn.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(salary), 0.0) FROM employees
+                 WHERE client_id = ?1 AND deleted_at IS NULL AND created_at < ?2 AND (termination_date IS NULL OR termination_date > ?3)",
+                params![client_id, end.to_rfc3339(), end.format("%Y-%m-%d").to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+        };
+
+        let count_between = |sql: &str, start: &DateTime<Utc>, end: &DateTime<Utc>| -> Result<i64> {
+            conn.query_row(sql, params![client_id, start.to_rfc3339(), end.to_rfc3339()], |row| row.get(0))
+        };
+
+        let tasks_sql = "SELECT COUNT(*) FROM tasks WHERE client_id = ?1 AND deleted_at IS NULL AND done = 1 AND completed_at >= ?2 AND completed_at < ?3";
+        let events_sql = "SELECT COUNT(*) FROM events WHERE client_id = ?1 AND deleted_at IS NULL AND created_at >= ?2 AND created_at < ?3";
+
+        let (current_employees, current_payroll) = headcount_at(&now)?;
+        let (previous_employees, previous_payroll) = headcount_at(&current_start)?;
+
+        Ok(DashboardTrends {
+            period_days: period.num_days(),
+            current_period_start: current_start,
+            previous_period_start: previous_start,
+            employees: TrendMetric::new(current_employees as f64, previous_employees as f64),
+            payroll: TrendMetric::new(current_payroll, previous_payroll),
+            tasks_completed: TrendMetric::new(
+                count_between(tasks_sql, &current_start, &now)? as f64,
+                count_between(tasks_sql, &previous_start, &current_start)? as f64,
+            ),
+            events: TrendMetric::new(
+                count_between(events_sql, &current_start, &now)? as f64,
+                count_between(events_sql, &previous_start, &current_start)? as f64,
+            ),
+        })
+    }
+
+    pub fn count_employees(&self, client_id: &str) -> Result<i64> {
+        let conn = self.reader();
+        conn.query_row("SELECT COUNT(*) FROM employees WHERE client_id = ?1 AND deleted_at IS NULL", params![client_id], |row| row.get(0))
+    }
+
+    pub fn count_tasks(&self, client_id: &str) -> Result<i64> {
+        let conn = self.reader();
+        conn.query_row("SELECT COUNT(*) FROM tasks WHERE client_id = ?1 AND deleted_at IS NULL", params![client_id], |row| row.get(0))
+    }
+
+    pub fn count_events(&self, client_id: &str) -> Result<i64> {
+        let conn = self.reader();
+        conn.query_row("SELECT COUNT(*) FROM events WHERE client_id = ?1 AND deleted_at IS NULL", params![client_id], |row| row.get(0))
+    }
+
+    pub fn create_document(&self, document: &Document, content: &[u8]) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO documents (id, client_id, name, size_bytes, file_type, uploaded_at, task_id, event_id, content)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                document.id,
+                document.client_id,
+                document.name,
+                document.size_bytes,
+                document.file_type,
+                document.uploaded_at.to_rfc3339(),
+                document.task_id,
+                document.event_id,
+                content,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_documents(&self, client_id: &str, page: &Page) -> Result<Vec<Document>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM documents WHERE client_id = ?1 ORDER BY uploaded_at DESC LIMIT ?2 OFFSET ?3", DOCUMENT_COLUMNS),
+        )?;
+        let document_iter = stmt.query_map(params![client_id, page.limit, page.offset], row_to_document)?;
+
+        let mut documents = Vec::new();
+        for doc in document_iter {
+            documents.push(doc?);
+        }
+        Ok(documents)
+    }
+
+    pub fn get_task_documents(&self, task_id: &str, client_id: &str) -> Result<Vec<Document>> {
+        self.query_documents(
+            &format!("SELECT {} FROM documents WHERE client_id = ?1 AND task_id = ?2 ORDER BY uploaded_at DESC", DOCUMENT_COLUMNS),
+            client_id,
+            task_id,
+        )
+    }
+
+    pub fn get_event_documents(&self, event_id: &str, client_id: &str) -> Result<Vec<Document>> {
+        self.query_documents(
+            &format!("SELECT {} FROM documents WHERE client_id = ?1 AND event_id = ?2 ORDER BY uploaded_at DESC", DOCUMENT_COLUMNS),
+            client_id,
+            event_id,
+        )
+    }
+
+    fn query_documents(&self, sql: &str, client_id: &str, parent_id: &str) -> Result<Vec<Document>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(sql)?;
+        let document_iter = stmt.query_map(params![client_id, parent_id], row_to_document)?;
+
+        let mut documents = Vec::new();
+        for doc in document_iter {
+            documents.push(doc?);
+        }
+        Ok(documents)
+    }
+
+    pub fn get_document_content(&self, id: &str, client_id: &str) -> Result<Option<(Document, Vec<u8>)>> {
+        let conn = self.reader();
+        conn.query_row(
+            &format!("SELECT {}, content FROM documents WHERE id = ?1 AND client_id = ?2", DOCUMENT_COLUMNS),
+            params![id, client_id],
+            |row| {
+                let content: Option<Vec<u8>> = row.get("content")?;
+                Ok((row_to_document(row)?, content.unwrap_or_default()))
+            },
+        )
+        .optional()
+    }
+
+    pub fn count_documents(&self, client_id: &str) -> Result<i64> {
+        let conn = self.reader();
+        count_client_rows(&conn, "documents", client_id)
+    }
+
+    pub fn get_summary(&self, client_id: &str) -> Result<DataSummary> {
+        Ok(DataSummary {
+            employees: self.count_employees(client_id)?,
+            tasks: self.count_tasks(client_id)?,
+            events: self.count_events(client_id)?,
+            documents: self.count_documents(client_id)?,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn count_sessions(&self, client_id: &str) -> Result<i64> {
+        let conn = self.reader();
+        count_client_rows(&conn, "sessions", client_id)
+    }
+
+    pub fn count_active_sessions(&self, now: DateTime<Utc>) -> Result<i64> {
+        let conn = self.reader();
+        conn.query_row("SELECT COUNT(*) FROM sessions WHERE expires_at > ?1", params![now.to_rfc3339()], |row| row.get(0))
+    }
+
+    pub fn get_deletion_impact(&self, client_id: &str) -> Result<AccountDeletionImpact> {
+        // One read transaction, so the preview is a single snapshot like the cascade it describes.
+        // Unlike count_employees and friends this includes soft-deleted rows, since the cascade removes them too.
+        let mut conn = self.reader();
+        let tx = conn.transaction()?;
+        let impact = AccountDeletionImpact {
+            employees: count_client_rows(&tx, "employees", client_id)?,
+            tasks: count_client_rows(&tx, "tasks", client_id)?,
+            events: count_client_rows(&tx, "events", client_id)?,
+            documents: count_client_rows(&tx, "documents", client_id)?,
+            sessions: count_client_rows(&tx, "sessions", client_id)?,
+        };
+        tx.commit()?;
+        Ok(impact)
+    }
+
+    // Callers confirm the account password first; get_deletion_impact previews exactly what this removes.
+    pub fn delete_client_cascade(&self, client_id: &str) -> Result<AccountDeletionImpact> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        for table in ["task_tags", "event_attendees", "event_reminders", "payment_events", "salary_history", "refresh_tokens", "password_history", "client_services", "client_platforms"] {
+            tx.execute(&format!("DELETE FROM {} WHERE client_id = ?1", table), params![client_id])?;
+        }
+        let impact = AccountDeletionImpact {
+            documents: tx.execute("DELETE FROM documents WHERE client_id = ?1", params![client_id])? as i64,
+            tasks: tx.execute("DELETE FROM tasks WHERE client_id = ?1", params![client_id])? as i64,
+            events: tx.execute("DELETE FROM events WHERE client_id = ?1", params![client_id])? as i64,
+            employees: tx.execute("DELETE FROM employees WHERE client_id = ?1", params![client_id])? as i64,
+            sessions: tx.execute("DELETE FROM sessions WHERE client_id = ?1", params![client_id])? as i64,
+        };
+        tx.execute("DELETE FROM clients WHERE id = ?1", params![client_id])?;
+
+        tx.commit()?;
+        Ok(impact)
+    }
+
+    pub fn reset_sandbox(&self, client_id: &str, seed: &SandboxSeed) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        // Documents survive a reset, so they're detached before the rows they point at go away.
+        tx.execute(
+            "UPDATE documents SET task_id = NULL, event_id = NULL WHERE client_id = ?1",
+            params![client_id],
+        )?;
+        for table in ["task_tags", "event_attendees", "event_reminders", "payment_events", "salary_history", "tasks", "events", "employees"] {
+            tx.execute(&format!("DELETE FROM {} WHERE client_id = ?1", table), params![client_id])?;
+        }
+
+        for employee in &seed.employees {
+            insert_employee(&tx, employee)?;
+        }
+        for task in &seed.tasks {
+            insert_task(&tx, task)?;
+        }
+        for event in &seed.events {
+            insert_event(&tx, event)?;
+        }
+
+        tx.commit()
+    }
+
+    #[allow(dead_code)]
+    pub fn check_health(&self) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let result: i32 = conn.query_row("SELECT 1", [], |r| r.get(0))?;
+        Ok(result == 1)
+    }
+
+    #[allow(dead_code)]
+    pub fn backup_db(&self, backup_path: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        conn.backup(rusqlite::DatabaseName::Main, Path::new(backup_path), None)?;
+        conn.backup(rusqlite::DatabaseName::Main, Path::new(backup_path), None::<fn(rusqlite::backup::Progress)>)?;
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub fn restore_db(&self, backup_path: &str) -> Result<()> {
         let mut conn = self.conn.lock().unwrap();
-        conn.restore(rusqlite::DatabaseName::Main, Path::new(backup_path), None)?;
+        conn.restore(rusqlite::DatabaseName::Main, Path::new(backup_path), None::<fn(rusqlite::backup::Progress)>)?;
         Ok(())
     }
 
-    pub fn vacuum(&self) -> Result<()> {
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    pub fn vacuum(&self) -> Result<VacuumReport> {
+        // Holding the connection lock for the whole run keeps concurrent vacuums (and writers) out.
         let conn = self.conn.lock().unwrap();
+        let size_before_bytes = self.file_size();
         conn.execute("VACUUM", [])?;
-        Ok(())
+        let size_after_bytes = self.file_size();
+
+        Ok(VacuumReport {
+            size_before_bytes,
+            size_after_bytes,
+            reclaimed_bytes: size_before_bytes.saturating_sub(size_after_bytes),
+        })
     }
 
+    fn file_size(&self) -> u64 {
+        std::fs::metadata(&self.db_path).map(|m| m.len()).unwrap_or(0)
+    }
+
+    #[allow(dead_code)]
     pub fn execute_raw(&self, query: &str) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
         conn.execute(query, [])
     }
 }
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::DbError(e.to_string())
+    }
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns: Vec<String> = stmt.query_map([], |row| row.get(1))?.collect::<Result<_>>()?;
+
+    if !columns.iter().any(|c| c == column) {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+fn parse_timestamp(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .unwrap_or_else(|_| DateTime::parse_from_rfc3339("1970-01-01T00:00:00Z").unwrap())
+        .with_timezone(&Utc)
+}
+
+fn row_to_client(row: &Row) -> Result<Client> {
+    let created_str: String = row.get("created_at")?;
+    let created_at = parse_timestamp(&created_str);
+    let last_login_str: Option<String> = row.get("last_login_at")?;
+    let deactivated_str: Option<String> = row.get("deactivated_at")?;
+
+    Ok(Client {
+        id: row.get("id")?,
+        business_name: row.get("business_name")?,
+        business_website: row.get("business_website")?,
+        business_sector: row.get("business_sector")?,
+        revenue: row.get("revenue")?,
+        goals: row.get("goals")?,
+        email: row.get("email")?,
+        job_title: row.get("job_title")?,
+        username: row.get("username")?,
+        password_hash: row.get("password_hash")?,
+        custom_goal_text: row.get("custom_goal_text")?,
+        other_service_text: row.get("other_service_text")?,
+        created_at,
+        last_login_at: last_login_str.as_deref().map(parse_timestamp),
+        deactivated_at: deactivated_str.as_deref().map(parse_timestamp),
+    })
+}
+
+fn row_to_task(row: &Row) -> Result<Task> {
+    let created_str: String = row.get("created_at")?;
+    let completed_str: Option<String> = row.get("completed_at")?;
+    let updated_str: Option<String> = row.get("updated_at")?;
+    let done_int: i32 = row.get("done")?;
+
+    Ok(Task {
+        id: row.get("id")?,
+        client_id: row.get("client_id")?,
+        title: row.get("title")?,
+        priority: row.get("priority")?,
+        position: row.get("position")?,
+        tags: Vec::new(),
+        assignee_id: row.get("assignee_id")?,
+        done: done_int == 1,
+        archived: row.get("archived")?,
+        created_at: parse_timestamp(&created_str),
+        completed_at: completed_str.as_deref().map(parse_timestamp),
+        updated_at: updated_str.as_deref().map(parse_timestamp),
+    })
+}
+
+// Listing queries select these instead of `*` so the content BLOB is only read on download.
+const DOCUMENT_COLUMNS: &str = "id, client_id, name, size_bytes, file_type, task_id, event_id, uploaded_at";
+// Ids per `IN (...)` list, well under SQLite's bound-parameter limit.
+const ID_BATCH_SIZE: usize = 500;
+
+fn row_to_document(row: &Row) -> Result<Document> {
+    let uploaded_str: String = row.get("uploaded_at")?;
+
+    Ok(Document {
+        id: row.get("id")?,
+        client_id: row.get("client_id")?,
+        name: row.get("name")?,
+        size_bytes: row.get("size_bytes")?,
+        file_type: row.get("file_type")?,
+        task_id: row.get("task_id")?,
+        event_id: row.get("event_id")?,
+        uploaded_at: parse_timestamp(&uploaded_str),
+    })
+}
+
+fn row_to_reminder(row: &Row) -> Result<EventReminder> {
+    let fire_str: String = row.get("fire_at")?;
+    let sent_str: Option<String> = row.get("sent_at")?;
+    let created_str: String = row.get("created_at")?;
+
+    Ok(EventReminder {
+        id: row.get("id")?,
+        event_id: row.get("event_id")?,
+        client_id: row.get("client_id")?,
+        minutes_before: row.get("minutes_before")?,
+        channel: row.get("channel")?,
+        fire_at: parse_timestamp(&fire_str),
+        sent_at: sent_str.as_deref().map(parse_timestamp),
+        created_at: parse_timestamp(&created_str),
+    })
+}
+
+fn push_password_history(conn: &Connection, client_id: &str, password_hash: &str, limit: u32) -> Result<()> {
+    if limit > 0 {
+        conn.execute(
+            "INSERT INTO password_history (client_id, password_hash, created_at) VALUES (?1, ?2, ?3)",
+            params![client_id, password_hash, Utc::now().to_rfc3339()],
+        )?;
+    }
+    conn.execute(
+        "DELETE FROM password_history WHERE client_id = ?1 AND rowid NOT IN (
+             SELECT rowid FROM password_history WHERE client_id = ?1 ORDER BY created_at DESC, rowid DESC LIMIT ?2
+         )",
+        params![client_id, limit],
+    )?;
+    Ok(())
+}
+
+fn load_employee(conn: &Connection, id: &str, client_id: &str) -> Result<Option<Employee>> {
+    let mut stmt = conn.prepare("SELECT * FROM employees WHERE id = ?1 AND client_id = ?2 AND deleted_at IS NULL")?;
+    let mut employee_iter = stmt.query_map(params![id, client_id], row_to_employee)?;
+
+    match employee_iter.next() {
+        Some(emp) => Ok(Some(emp?)),
+        None => Ok(None),
+    }
+}
+
+fn load_salary_history(conn: &Connection, employee_id: &str, client_id: &str) -> Result<Vec<SalaryChange>> {
+    let mut stmt = conn.prepare(
+        "SELECT employee_id, old_salary, new_salary, changed_at FROM salary_history
+         WHERE employee_id = ?1 AND client_id = ?2
+         ORDER BY changed_at ASC, rowid ASC",
+    )?;
+    let changes = stmt
+        .query_map(params![employee_id, client_id], |row| {
+            let changed_str: String = row.get(3)?;
+            Ok(SalaryChange {
+                employee_id: row.get(0)?,
+                old_salary: row.get(1)?,
+                new_salary: row.get(2)?,
+                changed_at: parse_timestamp(&changed_str),
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(changes)
+}
+
+fn load_payment_events(conn: &Connection, employee_id: &str, client_id: &str) -> Result<Vec<PaymentEvent>> {
+    let mut stmt = conn.prepare(
+        "SELECT employee_id, paid, changed_at FROM payment_events
+         WHERE employee_id = ?1 AND client_id = ?2
+         ORDER BY changed_at ASC, rowid ASC",
+    )?;
+    let events = stmt
+        .query_map(params![employee_id, client_id], |row| {
+            let changed_str: String = row.get(2)?;
+            Ok(PaymentEvent {
+                employee_id: row.get(0)?,
+                paid: row.get(1)?,
+                changed_at: parse_timestamp(&changed_str),
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(events)
+}
+
+fn load_employee_tasks(conn: &Connection, employee_id: &str, client_id: &str) -> Result<Vec<Task>> {
+    let priorities = load_task_priorities(conn, client_id)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT * FROM tasks WHERE assignee_id = ? AND client_id = ? AND deleted_at IS NULL
+         ORDER BY {}, position ASC, created_at DESC",
+        priority_rank_sql(&priorities),
+    ))?;
+    let mut values: Vec<&dyn ToSql> = vec![&employee_id, &client_id];
+    values.extend(priorities.iter().map(|p| p as &dyn ToSql));
+    let mut tasks = stmt.query_map(values.as_slice(), row_to_task)?.collect::<Result<Vec<_>>>()?;
+    load_task_tags(conn, client_id, &mut tasks)?;
+    Ok(tasks)
+}
+
+fn load_employee_events(conn: &Connection, employee_id: &str, client_id: &str) -> Result<Vec<Event>> {
+    let mut stmt = conn.prepare(
+        "SELECT e.* FROM events e
+         JOIN event_attendees a ON a.event_id = e.id
+         WHERE a.employee_id = ?1 AND a.client_id = ?2 AND e.client_id = ?2 AND e.deleted_at IS NULL
+         ORDER BY e.start_date ASC",
+    )?;
+    let event_iter = stmt.query_map(params![employee_id, client_id], row_to_event)?;
+
+    let mut events = Vec::new();
+    for ev in event_iter {
+        events.push(ev?);
+    }
+    load_attendees(conn, client_id, &mut events)?;
+    Ok(events)
+}
+
+fn insert_employee(conn: &Connection, employee: &Employee) -> Result<()> {
+    conn.execute(
+        "INSERT INTO employees (id, client_id, name, title, salary, status, paid, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            employee.id,
+            employee.client_id,
+            employee.name,
+            employee.title,
+            employee.salary,
+            employee.status,
+            if employee.paid { 1 } else { 0 },
+            employee.created_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_task(conn: &Connection, task: &Task) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tasks (id, client_id, title, priority, position, assignee_id, done, created_at, completed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            task.id,
+            task.client_id,
+            task.title,
+            task.priority,
+            task.position,
+            task.assignee_id,
+            if task.done { 1 } else { 0 },
+            task.created_at.to_rfc3339(),
+            task.completed_at.map(|t| t.to_rfc3339()),
+        ],
+    )?;
+    for tag in &task.tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO task_tags (task_id, client_id, tag) VALUES (?1, ?2, ?3)",
+            params![task.id, task.client_id, tag],
+        )?;
+    }
+    Ok(())
+}
+
+fn insert_event(conn: &Connection, event: &Event) -> Result<()> {
+    conn.execute(
+        "INSERT INTO events (id, client_id, title, description, start_date, start_time, end_date, end_time, color, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            event.id,
+            event.client_id,
+            event.title,
+            event.description,
+            event.start_date,
+            event.start_time,
+            event.end_date,
+            event.end_time,
+            event.color,
+            event.created_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_session(conn: &Connection, session: &SessionData) -> Result<()> {
+    conn.execute(
+        "INSERT INTO sessions (token, client_id, created_at, expires_at, refresh_family) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            session.token,
+            session.client_id,
+            session.created_at.to_rfc3339(),
+            session.expires_at.to_rfc3339(),
+            session.refresh_family,
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_refresh_token(conn: &Connection, token: &RefreshTokenData) -> Result<()> {
+    conn.execute(
+        "INSERT INTO refresh_tokens (token_hash, family_id, client_id, created_at, expires_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            token.token_hash,
+            token.family_id,
+            token.client_id,
+            token.created_at.to_rfc3339(),
+            token.expires_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+// A client row that has gone missing (e.g. deleted mid-session) falls back to the defaults too.
+fn load_task_priorities(conn: &Connection, client_id: &str) -> Result<Vec<String>> {
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT task_priorities FROM clients WHERE id = ?1",
+            params![client_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+
+    Ok(match stored {
+        Some(list) if !list.is_empty() => list.split(',').map(|p| p.to_string()).collect(),
+        _ => DEFAULT_TASK_PRIORITIES.iter().map(|p| p.to_string()).collect(),
+    })
+}
+
+/// Builds a `CASE` expression ranking `priority` by its index in the client's configured list,
+/// with one `?` per entry. Priorities no longer in the list sort after all known ones.
+fn priority_rank_sql(priorities: &[String]) -> String {
+    let mut sql = String::from("CASE priority");
+    for i in 0..priorities.len() {
+        sql.push_str(&format!(" WHEN ? THEN {}", i));
+    }
+    sql.push_str(&format!(" ELSE {} END", priorities.len()));
+    sql
+}
+
+fn load_task_tags(conn: &Connection, client_id: &str, tasks: &mut [Task]) -> Result<()> {
+    let ids: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+    let mut by_task: HashMap<String, Vec<String>> = HashMap::new();
+
+    for batch in ids.chunks(ID_BATCH_SIZE) {
+        let placeholders = vec!["?"; batch.len()].join(", ");
+        let sql = format!(
+            "SELECT task_id, tag FROM task_tags WHERE client_id = ? AND task_id IN ({}) ORDER BY tag ASC",
+            placeholders
+        );
+        let mut values: Vec<&dyn ToSql> = vec![&client_id];
+        values.extend(batch.iter().map(|id| id as &dyn ToSql));
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(values.as_slice(), |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (task_id, tag) = row?;
+            by_task.entry(task_id).or_default().push(tag);
+        }
+    }
+
+    for task in tasks.iter_mut() {
+        task.tags = by_task.remove(&task.id).unwrap_or_default();
+    }
+    Ok(())
+}
+
+fn row_to_event(row: &Row) -> Result<Event> {
+    let created_str: String = row.get("created_at")?;
+    let updated_str: Option<String> = row.get("updated_at")?;
+
+    Ok(Event {
+        id: row.get("id")?,
+        client_id: row.get("client_id")?,
+        title: row.get("title")?,
+        description: row.get("description")?,
+        start_date: row.get("start_date")?,
+        start_time: row.get("start_time")?,
+        end_date: row.get("end_date")?,
+        end_time: row.get("end_time")?,
+        color: row.get("color")?,
+        attendees: Vec::new(),
+        created_at: parse_timestamp(&created_str),
+        updated_at: updated_str.as_deref().map(parse_timestamp),
+    })
+}
+
+// A family is one login and the sessions its refresh token has issued; sessions from before
+// refresh tokens have no family and count on their own. Only live families count towards the
+// cap, ranked by their newest session, so a busy login is never evicted for an idle one.
+fn evict_excess_families(conn: &Connection, client_id: &str, max_sessions: u32) -> Result<usize> {
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(refresh_family, token) AS family FROM sessions
+         WHERE client_id = ?1 AND expires_at > ?2
+         GROUP BY family
+         ORDER BY MAX(created_at) DESC, MAX(rowid) DESC
+         LIMIT -1 OFFSET ?3",
+    )?;
+    let excess = stmt
+        .query_map(params![client_id, Utc::now().to_rfc3339(), max_sessions], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+
+    for family in &excess {
+        conn.execute("UPDATE refresh_tokens SET revoked = 1 WHERE family_id = ?1", params![family])?;
+        conn.execute(
+            "DELETE FROM sessions WHERE client_id = ?1 AND COALESCE(refresh_family, token) = ?2",
+            params![client_id, family],
+        )?;
+    }
+    Ok(excess.len())
+}
+
+fn revoke_client_sessions(conn: &Connection, client_id: &str, except: Option<&str>) -> Result<usize> {
+    conn.execute(
+        "UPDATE refresh_tokens SET revoked = 1
+         WHERE client_id = ?1 AND family_id IS NOT (SELECT refresh_family FROM sessions WHERE token = ?2)",
+        params![client_id, except],
+    )?;
+    conn.execute("DELETE FROM sessions WHERE client_id = ?1 AND token IS NOT ?2", params![client_id, except])
+}
+
+fn count_client_rows(conn: &Connection, table: &str, client_id: &str) -> Result<i64> {
+    conn.query_row(&format!("SELECT COUNT(*) FROM {} WHERE client_id = ?1", table), params![client_id], |row| row.get(0))
+}
+
+fn touch_task(conn: &Connection, task_id: &str, client_id: &str) -> Result<usize> {
+    conn.execute(
+        "UPDATE tasks SET updated_at = ?1 WHERE id = ?2 AND client_id = ?3",
+        params![Utc::now().to_rfc3339(), task_id, client_id],
+    )
+}
+
+fn touch_event(conn: &Connection, event_id: &str, client_id: &str) -> Result<usize> {
+    conn.execute(
+        "UPDATE events SET updated_at = ?1 WHERE id = ?2 AND client_id = ?3",
+        params![Utc::now().to_rfc3339(), event_id, client_id],
+    )
+}
+
+fn load_attendees(conn: &Connection, client_id: &str, events: &mut [Event]) -> Result<()> {
+    let ids: Vec<String> = events.iter().map(|e| e.id.clone()).collect();
+    let mut by_event: HashMap<String, Vec<EventAttendee>> = HashMap::new();
+
+    for batch in ids.chunks(ID_BATCH_SIZE) {
+        let placeholders = vec!["?"; batch.len()].join(", ");
+        let sql = format!(
+            "SELECT a.event_id, a.employee_id, e.name FROM event_attendees a
+             JOIN employees e ON e.id = a.employee_id
+             WHERE a.client_id = ? AND a.event_id IN ({})
+             ORDER BY e.name ASC",
+            placeholders
+        );
+        let mut values: Vec<&dyn ToSql> = vec![&client_id];
+        values.extend(batch.iter().map(|id| id as &dyn ToSql));
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(values.as_slice(), |row| {
+            Ok((row.get::<_, String>(0)?, EventAttendee { employee_id: row.get(1)?, name: row.get(2)? }))
+        })?;
+        for row in rows {
+            let (event_id, attendee) = row?;
+            by_event.entry(event_id).or_default().push(attendee);
+        }
+    }
+
+    for event in events.iter_mut() {
+        event.attendees = by_event.remove(&event.id).unwrap_or_default();
+    }
+    Ok(())
+}
+
+fn row_to_employee(row: &Row) -> Result<Employee> {
+    let created_str: String = row.get("created_at")?;
+    let updated_str: Option<String> = row.get("updated_at")?;
+    let paid_int: i32 = row.get("paid")?;
+
+    Ok(Employee {
+        id: row.get("id")?,
+        client_id: row.get("client_id")?,
+        name: row.get("name")?,
+        title: row.get("title")?,
+        salary: row.get("salary")?,
+        status: row.get("status")?,
+        paid: paid_int == 1,
+        termination_date: row.get("termination_date")?,
+        created_at: parse_timestamp(&created_str),
+        updated_at: updated_str.as_deref().map(parse_timestamp),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UploadDocumentQuery;
+
+    fn storage() -> Storage {
+        Storage::new(":memory:").unwrap()
+    }
+
+    fn seed_client(storage: &Storage, username: &str) -> Client {
+        let client = Client::new(
+            "Acme".to_string(),
+            "https://acme.test".to_string(),
+            "Retail".to_string(),
+            "Pre-revenue".to_string(),
+            "Grow".to_string(),
+            format!("{}@acme.test", username),
+            "Owner".to_string(),
+            username.to_string(),
+            "hash".to_string(),
+        );
+        storage.create_client(&client, &[], &[]).unwrap();
+        client
+    }
+
+    fn seed_employee(storage: &Storage, client_id: &str, name: &str, salary: f64, status: &str) -> Employee {
+        let employee = Employee::new(client_id.to_string(), name.to_string(), "Clerk".to_string(), salary, status.to_string());
+        storage.create_employee(&employee).unwrap();
+        employee
+    }
+
+    fn seed_event(storage: &Storage, client_id: &str, title: &str) -> Event {
+        let event = Event::new(
+            client_id.to_string(),
+            title.to_string(),
+            String::new(),
+            "2030-01-01".to_string(),
+            "09:00".to_string(),
+            "2030-01-01".to_string(),
+            "10:00".to_string(),
+            "#336699".to_string(),
+        );
+        storage.create_event(&event).unwrap();
+        event
+    }
+
+    fn seed_document(storage: &Storage, client_id: &str, name: &str, task_id: Option<&str>, event_id: Option<&str>) -> Document {
+        let query = UploadDocumentQuery {
+            name: name.to_string(),
+            task_id: task_id.map(str::to_string),
+            event_id: event_id.map(str::to_string),
+        };
+        let document = Document::new(client_id.to_string(), &query, "text/plain".to_string(), 5);
+        storage.create_document(&document, b"hello").unwrap();
+        document
+    }
+
+    #[test]
+    fn summary_counts_live_rows_for_the_client_only() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let other = seed_client(&storage, "other");
+        seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+        let gone = seed_employee(&storage, &client.id, "Bob", 1000.0, "Active");
+        storage.delete_employee(&gone.id, &client.id).unwrap();
+        seed_employee(&storage, &other.id, "Eve", 1000.0, "Active");
+        storage.create_task(&Task::new(client.id.clone(), "Ship".to_string(), "High".to_string())).unwrap();
+        seed_event(&storage, &client.id, "Kickoff");
+        seed_document(&storage, &client.id, "notes.txt", None, None);
+        seed_document(&storage, &client.id, "plan.txt", None, None);
+        seed_document(&storage, &other.id, "theirs.txt", None, None);
+
+        let summary = storage.get_summary(&client.id).unwrap();
+        assert_eq!((summary.employees, summary.tasks, summary.events, summary.documents), (1, 1, 1, 2));
+
+        let empty = storage.get_summary(&seed_client(&storage, "new").id).unwrap();
+        assert_eq!((empty.employees, empty.tasks, empty.events, empty.documents), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn trends_compare_current_and_previous_period() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+        seed_employee(&storage, &client.id, "Bob", 2000.0, "Active");
+        seed_event(&storage, &client.id, "Kickoff");
+        let task = Task::new(client.id.clone(), "Ship".to_string(), "High".to_string());
+        storage.create_task(&task).unwrap();
+        storage.update_task_status(&task.id, &client.id, true).unwrap();
+
+        let week = Duration::days(7);
+        let trends = storage.get_dashboard_trends(&client.id, Utc::now() + Duration::seconds(1), week).unwrap();
+        assert_eq!((trends.employees.current, trends.employees.previous), (2.0, 0.0));
+        assert_eq!(trends.payroll.current, 3000.0);
+        assert_eq!((trends.tasks_completed.current, trends.tasks_completed.previous), (1.0, 0.0));
+        assert_eq!((trends.events.current, trends.events.previous), (1.0, 0.0));
+        assert_eq!(trends.events.percent_change, None);
+
+        let trends = storage.get_dashboard_trends(&client.id, Utc::now() + Duration::days(8), week).unwrap();
+        assert_eq!((trends.employees.current, trends.employees.previous), (2.0, 2.0));
+        assert_eq!((trends.tasks_completed.current, trends.tasks_completed.previous), (0.0, 1.0));
+        assert_eq!(trends.events.delta, -1.0);
+        assert_eq!(trends.events.percent_change, Some(-100.0));
+    }
+
+    #[test]
+    fn attendees_are_added_once_and_only_for_owned_rows() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let other = seed_client(&storage, "other");
+        let event = seed_event(&storage, &client.id, "Standup");
+        let ada = seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+        let bob = seed_employee(&storage, &client.id, "Bob", 1000.0, "Active");
+        let stranger = seed_employee(&storage, &other.id, "Eve", 1000.0, "Active");
+
+        let ids = vec![ada.id.clone(), bob.id.clone()];
+        assert_eq!(storage.add_event_attendees(&event.id, &client.id, &ids).unwrap(), AttendeeOutcome::Added(2));
+        assert_eq!(storage.add_event_attendees(&event.id, &client.id, &ids).unwrap(), AttendeeOutcome::Added(0));
+
+        let carol = seed_employee(&storage, &client.id, "Carol", 1000.0, "Active");
+        let ids = vec![carol.id.clone(), stranger.id.clone()];
+        assert_eq!(
+            storage.add_event_attendees(&event.id, &client.id, &ids).unwrap(),
+            AttendeeOutcome::EmployeeNotFound(stranger.id.clone()),
+        );
+        let attendees = storage.get_event(&event.id, &client.id).unwrap().unwrap().attendees;
+        assert_eq!(attendees.len(), 2);
+        assert!(attendees.iter().all(|a| a.employee_id != carol.id));
+    }
+
+    #[test]
+    fn listed_events_carry_only_their_own_attendees() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let standup = seed_event(&storage, &client.id, "Standup");
+        let retro = seed_event(&storage, &client.id, "Retro");
+        let ada = seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+        let bob = seed_employee(&storage, &client.id, "Bob", 1000.0, "Active");
+        storage.add_event_attendees(&standup.id, &client.id, std::slice::from_ref(&ada.id)).unwrap();
+        storage.add_event_attendees(&retro.id, &client.id, &[ada.id.clone(), bob.id.clone()]).unwrap();
+
+        for event in storage.get_events(&client.id, &Page::all()).unwrap() {
+            let names: Vec<&str> = event.attendees.iter().map(|a| a.name.as_str()).collect();
+            let expected: &[&str] = if event.id == standup.id { &["Ada"] } else { &["Ada", "Bob"] };
+            assert_eq!(names, expected);
+        }
+
+        let page = storage.get_events(&client.id, &Page { limit: 1, offset: 1 }).unwrap();
+        assert_eq!(page.len(), 1);
+        assert!(!page[0].attendees.is_empty());
+    }
+
+    #[test]
+    fn attendees_are_not_added_to_deleted_rows() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let event = seed_event(&storage, &client.id, "Standup");
+        let ada = seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+
+        storage.delete_employee(&ada.id, &client.id).unwrap();
+        assert_eq!(
+            storage.add_event_attendees(&event.id, &client.id, std::slice::from_ref(&ada.id)).unwrap(),
+            AttendeeOutcome::EmployeeNotFound(ada.id.clone()),
+        );
+
+        storage.delete_event(&event.id, &client.id).unwrap();
+        assert_eq!(storage.add_event_attendees(&event.id, &client.id, &[]).unwrap(), AttendeeOutcome::EventNotFound);
+    }
+
+    fn seed_task(storage: &Storage, client_id: &str, title: &str, priority: &str, position: f64, age_mins: i64) -> Task {
+        let mut task = Task::new(client_id.to_string(), title.to_string(), priority.to_string());
+        task.position = position;
+        task.created_at = Utc::now() - Duration::minutes(age_mins);
+        storage.create_task(&task).unwrap();
+        task
+    }
+
+    fn titles(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn tasks_default_to_board_order_and_newest_is_opt_in() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        storage.set_task_priorities(&client.id, &["Urgent".to_string(), "Low".to_string(), "Medium".to_string()]).unwrap();
+        seed_task(&storage, &client.id, "low", "Low", 0.0, 40);
+        seed_task(&storage, &client.id, "urgent-second", "Urgent", 1.0, 30);
+        seed_task(&storage, &client.id, "urgent-first", "Urgent", 0.0, 20);
+        seed_task(&storage, &client.id, "medium", "Medium", 0.0, 10);
+        seed_task(&storage, &client.id, "retired", "Someday", 0.0, 5);
+
+        let board = storage.get_tasks(&client.id, &TaskQuery::default(), &Page::all()).unwrap();
+        assert_eq!(titles(&board), ["urgent-first", "urgent-second", "low", "medium", "retired"]);
+
+        let query = TaskQuery { sort: TaskSort::Newest, ..TaskQuery::default() };
+        let newest = storage.get_tasks(&client.id, &query, &Page::all()).unwrap();
+        assert_eq!(titles(&newest), ["retired", "medium", "urgent-first", "urgent-second", "low"]);
+    }
+
+    #[test]
+    fn employee_tasks_follow_configured_priorities() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let ada = seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+        for (title, priority) in [("high", "High"), ("low", "Low"), ("medium", "Medium")] {
+            let mut task = Task::new(client.id.clone(), title.to_string(), priority.to_string());
+            task.assignee_id = Some(ada.id.clone());
+            storage.create_task(&task).unwrap();
+        }
+
+        let tasks = storage.get_employee_detail(&ada.id, &client.id).unwrap().unwrap().tasks;
+        assert_eq!(titles(&tasks), ["high", "medium", "low"]);
+
+        storage.set_task_priorities(&client.id, &["Low".to_string(), "Medium".to_string(), "High".to_string()]).unwrap();
+        let tasks = storage.get_employee_detail(&ada.id, &client.id).unwrap().unwrap().tasks;
+        assert_eq!(titles(&tasks), ["low", "medium", "high"]);
+    }
+
+    #[test]
+    fn terminated_employees_are_excluded_from_headcount_and_payroll() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+        seed_employee(&storage, &client.id, "Bob", 2000.0, "On Leave");
+        let leaver = seed_employee(&storage, &client.id, "Carol", 4000.0, "Active");
+        seed_employee(&storage, &client.id, "Dan", 8000.0, TERMINATED_STATUS);
+
+        storage.update_employee_salary(&leaver.id, &client.id, 5000.0).unwrap();
+        let stats = storage.get_dashboard_stats(&client.id).unwrap();
+        assert_eq!((stats.total_employees, stats.monthly_payroll), (3, 8000.0));
+
+        let history = |storage: &Storage| {
+            let detail = storage.get_employee_detail(&leaver.id, &client.id).unwrap().unwrap();
+            detail.salary_history.iter().map(|c| (c.old_salary, c.new_salary)).collect::<Vec<_>>()
+        };
+        assert_eq!(history(&storage), [(4000.0, 5000.0)]);
+
+        storage.terminate_employee(&leaver.id, &client.id, "2030-01-31").unwrap();
+        let stats = storage.get_dashboard_stats(&client.id).unwrap();
+        assert_eq!((stats.total_employees, stats.monthly_payroll), (2, 3000.0));
+
+        // Terminated staff stay on the books: still listed, history intact.
+        let employees = storage.get_employees(&client.id, &EmployeeQuery::default(), &Page::all()).unwrap();
+        let carol = employees.iter().find(|e| e.id == leaver.id).unwrap();
+        assert_eq!(carol.status, TERMINATED_STATUS);
+        assert_eq!(employees.len(), 4);
+        assert_eq!(history(&storage), [(4000.0, 5000.0)]);
+    }
+
+    fn seed_tagged_task(storage: &Storage, client_id: &str, title: &str, priority: &str, tags: &[&str]) -> Task {
+        let mut task = Task::new(client_id.to_string(), title.to_string(), priority.to_string());
+        task.tags = tags.iter().map(|t| t.to_string()).collect();
+        storage.create_task(&task).unwrap();
+        task
+    }
+
+    #[test]
+    fn tasks_filter_by_tag() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let other = seed_client(&storage, "other");
+        seed_tagged_task(&storage, &client.id, "invoice", "High", &["billing", "q1"]);
+        seed_tagged_task(&storage, &client.id, "hire", "Low", &["hr"]);
+        seed_tagged_task(&storage, &other.id, "foreign", "High", &["billing"]);
+
+        let query = TaskQuery { tag: Some("billing".to_string()), ..TaskQuery::default() };
+        let tasks = storage.get_tasks(&client.id, &query, &Page::all()).unwrap();
+        assert_eq!(titles(&tasks), ["invoice"]);
+        assert_eq!(tasks[0].tags, ["billing", "q1"]);
+
+        let query = TaskQuery { tag: Some("missing".to_string()), ..TaskQuery::default() };
+        assert!(storage.get_tasks(&client.id, &query, &Page::all()).unwrap().is_empty());
+
+        let page = storage.get_tasks(&client.id, &TaskQuery::default(), &Page { limit: 1, offset: 1 }).unwrap();
+        assert_eq!(titles(&page), ["hire"]);
+        assert_eq!(page[0].tags, ["hr"]);
+    }
+
+    #[test]
+    fn facets_list_values_from_live_tasks_only() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let other = seed_client(&storage, "other");
+        seed_tagged_task(&storage, &client.id, "invoice", "High", &["billing", "q1"]);
+        seed_tagged_task(&storage, &client.id, "audit", "High", &["billing"]);
+        let gone = seed_tagged_task(&storage, &client.id, "old", "Medium", &["legacy"]);
+        storage.delete_task(&gone.id, &client.id).unwrap();
+        seed_tagged_task(&storage, &other.id, "foreign", "Low", &["secret"]);
+
+        let facets = storage.get_task_facets(&client.id).unwrap();
+        assert_eq!(facets.priorities, ["High"]);
+        assert_eq!(facets.tags, ["billing", "q1"]);
+    }
+
+    #[test]
+    fn task_priorities_fall_back_to_defaults() {
+        let storage = storage();
+        let defaults: Vec<String> = DEFAULT_TASK_PRIORITIES.iter().map(|p| p.to_string()).collect();
+        assert_eq!(storage.get_task_priorities("no-such-client").unwrap(), defaults);
+
+        let client = seed_client(&storage, "acme");
+        assert_eq!(storage.get_task_priorities(&client.id).unwrap(), defaults);
+
+        let custom = vec!["Now".to_string(), "Soon".to_string(), "Someday".to_string()];
+        storage.set_task_priorities(&client.id, &custom).unwrap();
+        assert_eq!(storage.get_task_priorities(&client.id).unwrap(), custom);
+    }
+
+    #[test]
+    fn read_replica_rejects_writes_but_sees_commits() {
+        let path = std::env::temp_dir().join(format!("qads-replica-{}.db", uuid::Uuid::new_v4()));
+        let storage = Storage::new(path.to_str().unwrap()).unwrap().with_read_replica().unwrap();
+        let client = seed_client(&storage, "acme");
+
+        let err = storage
+            .reader()
+            .execute("INSERT INTO tasks (id, client_id, title) VALUES ('t1', 'c1', 'sneaky')", [])
+            .unwrap_err();
+        assert_eq!(err.sqlite_error_code(), Some(rusqlite::ErrorCode::ReadOnly));
+
+        let seen: String = storage
+            .reader()
+            .query_row("SELECT username FROM clients WHERE id = ?1", [&client.id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(seen, "acme");
+
+        drop(storage);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn dashboard_completion_rate() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        assert_eq!(storage.get_dashboard_stats(&client.id).unwrap().tasks_completion_rate, 0.0);
+
+        let tasks: Vec<Task> = (0..4).map(|i| seed_task(&storage, &client.id, &format!("t{}", i), "Medium", i as f64, i)).collect();
+        storage.update_task_status(&tasks[0].id, &client.id, true).unwrap();
+        let stats = storage.get_dashboard_stats(&client.id).unwrap();
+        assert_eq!(stats.active_tasks, 3);
+        assert_eq!(stats.tasks_completion_rate, 0.25);
+    }
+
+    #[test]
+    fn cleanup_deletes_only_live_done_archived_tasks() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let cleared = seed_task(&storage, &client.id, "cleared", "Medium", 0.0, 0);
+        let open_archived = seed_task(&storage, &client.id, "open archived", "Medium", 1.0, 0);
+        let done_visible = seed_task(&storage, &client.id, "done visible", "Medium", 2.0, 0);
+        let in_trash = seed_task(&storage, &client.id, "in trash", "Medium", 3.0, 0);
+        for task in [&cleared, &done_visible, &in_trash] {
+            storage.update_task_status(&task.id, &client.id, true).unwrap();
+        }
+        for task in [&cleared, &open_archived, &in_trash] {
+            storage.set_task_archived(&task.id, &client.id, true).unwrap();
+        }
+        storage.delete_task(&in_trash.id, &client.id).unwrap();
+
+        assert_eq!(storage.delete_archived_completed_tasks(&client.id).unwrap(), 1);
+        let remaining: Vec<String> = storage
+            .reader()
+            .prepare("SELECT title FROM tasks ORDER BY title")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(remaining, ["done visible", "in trash", "open archived"]);
+        assert_eq!(storage.purge_task(&in_trash.id, &client.id).unwrap(), PurgeOutcome::Purged);
+    }
+
+    #[test]
+    fn payroll_breakdown_totals_salaries_per_normalized_status() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+        seed_employee(&storage, &client.id, "Bob", 500.0, " active ");
+        seed_employee(&storage, &client.id, "Cy", 250.0, "On Leave");
+        seed_employee(&storage, &client.id, "Di", 100.0, "Terminated");
+        let gone = seed_employee(&storage, &client.id, "Ed", 9999.0, "Active");
+        storage.delete_employee(&gone.id, &client.id).unwrap();
+
+        let breakdown = storage.get_payroll_breakdown(&client.id).unwrap();
+        let groups: Vec<(&str, i64, f64)> = breakdown.groups.iter().map(|g| (g.status.as_str(), g.employees, g.salary_total)).collect();
+        assert_eq!(groups, [("Active", 2, 1500.0), ("On Leave", 1, 250.0), ("Terminated", 1, 100.0)]);
+        assert_eq!(breakdown.salary_total, 1850.0);
+    }
+
+    #[test]
+    fn activity_interleaves_updates_with_creations() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let ada = seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+        let standup = seed_event(&storage, &client.id, "Standup");
+        let ship = seed_task(&storage, &client.id, "Ship", "High", 0.0, 0);
+        let bob = seed_employee(&storage, &client.id, "Bob", 800.0, "Active");
+
+        storage.update_employee_salary(&ada.id, &client.id, 1200.0).unwrap();
+        storage.add_event_attendees(&standup.id, &client.id, std::slice::from_ref(&bob.id)).unwrap();
+
+        let entries = |page: Page| -> Vec<(String, String)> {
+            storage.get_activity(&client.id, &page).unwrap().into_iter().map(|item| (item.title, item.action)).collect()
+        };
+        let pair = |title: &str, action: &str| (title.to_string(), action.to_string());
+        assert_eq!(
+            entries(Page::all()),
+            [pair("Standup", "updated"), pair("Ada", "updated"), pair("Bob", "created"), pair("Ship", "created")],
+        );
+        assert_eq!(entries(Page { limit: 2, offset: 1 }), [pair("Ada", "updated"), pair("Bob", "created")]);
+
+        storage.update_employee_paid_status(&bob.id, &client.id, true).unwrap();
+        storage.terminate_employee(&ada.id, &client.id, "2030-01-31").unwrap();
+        assert_eq!(entries(Page { limit: 2, offset: 0 }), [pair("Ada", "updated"), pair("Bob", "updated")]);
+
+        storage.update_task_status(&ship.id, &client.id, true).unwrap();
+        assert_eq!(entries(Page { limit: 1, offset: 0 }), [pair("Ship", "completed")]);
+        storage.set_task_archived(&ship.id, &client.id, true).unwrap();
+        assert_eq!(entries(Page { limit: 1, offset: 0 }), [pair("Ship", "updated")]);
+        storage.terminate_employee(&ada.id, &client.id, "2030-02-28").unwrap();
+        storage.reorder_task(&ship.id, &client.id, "Low", 3.0).unwrap();
+        assert_eq!(entries(Page { limit: 1, offset: 0 }), [pair("Ship", "updated")]);
+        storage.terminate_employee(&ada.id, &client.id, "2030-03-31").unwrap();
+        storage.bulk_tag_tasks(std::slice::from_ref(&ship.id), &client.id, "q1", TagAction::Add).unwrap();
+        assert_eq!(entries(Page { limit: 1, offset: 0 }), [pair("Ship", "updated")]);
+        storage.terminate_employee(&ada.id, &client.id, "2030-04-30").unwrap();
+        storage.set_task_assignee(&ship.id, &client.id, Some(&bob.id)).unwrap();
+        assert_eq!(entries(Page { limit: 1, offset: 0 }), [pair("Ship", "updated")]);
+    }
+
+    #[test]
+    fn deletion_preview_matches_what_the_cascade_removes() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let other = seed_client(&storage, "other");
+        let ada = seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+        let gone = seed_employee(&storage, &client.id, "Ed", 900.0, "Active");
+        storage.delete_employee(&gone.id, &client.id).unwrap();
+        let task = seed_task(&storage, &client.id, "Ship", "High", 0.0, 0);
+        let event = seed_event(&storage, &client.id, "Standup");
+        storage.add_event_attendees(&event.id, &client.id, std::slice::from_ref(&ada.id)).unwrap();
+        seed_document(&storage, &client.id, "notes.txt", Some(&task.id), None);
+        seed_employee(&storage, &other.id, "Eve", 1000.0, "Active");
+
+        let preview = storage.get_deletion_impact(&client.id).unwrap();
+        assert_eq!(preview.employees, 2);
+        let removed = storage.delete_client_cascade(&client.id).unwrap();
+        assert_eq!(serde_json::to_value(&removed).unwrap(), serde_json::to_value(&preview).unwrap());
+
+        assert!(storage.get_client_by_id(&client.id).unwrap().is_none());
+        assert_eq!(storage.get_deletion_impact(&client.id).unwrap().employees, 0);
+        assert_eq!(storage.get_deletion_impact(&other.id).unwrap().employees, 1);
+    }
+
+    #[test]
+    fn payment_changes_are_recorded_in_order_and_no_ops_are_skipped() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let ada = seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+
+        assert_eq!(storage.update_employee_paid_status(&ada.id, &client.id, false).unwrap(), 1);
+        assert!(storage.get_payment_events(&ada.id, &client.id).unwrap().is_empty());
+        assert!(storage.get_employee(&ada.id, &client.id).unwrap().unwrap().updated_at.is_none());
+
+        storage.update_employee_paid_status(&ada.id, &client.id, true).unwrap();
+        storage.update_employee_paid_status(&ada.id, &client.id, true).unwrap();
+        storage.update_employee_paid_status(&ada.id, &client.id, false).unwrap();
+
+        let history = storage.get_payment_events(&ada.id, &client.id).unwrap();
+        assert_eq!(history.iter().map(|e| e.paid).collect::<Vec<_>>(), [true, false]);
+        assert!(history[0].changed_at <= history[1].changed_at);
+        assert_eq!(storage.update_employee_paid_status("missing", &client.id, true).unwrap(), 0);
+    }
+
+    #[test]
+    fn employee_detail_bundles_related_records_for_the_owner_only() {
+        let storage = storage();
+        let client = seed_client(&storage, "acme");
+        let other = seed_client(&storage, "other");
+        let ada = seed_employee(&storage, &client.id, "Ada", 1000.0, "Active");
+        let mut task = Task::new(client.id.clone(), "Ship".to_string(), "High".to_string());
+        task.assignee_id = Some(ada.id.clone());
+        storage.create_task(&task).unwrap();
+        let event = seed_event(&storage, &client.id, "Standup");
+        storage.add_event_attendees(&event.id, &client.id, std::slice::from_ref(&ada.id)).unwrap();
+        storage.update_employee_salary(&ada.id, &client.id, 1100.0).unwrap();
+        storage.update_employee_paid_status(&ada.id, &client.id, true).unwrap();
+
+        let detail = storage.get_employee_detail(&ada.id, &client.id).unwrap().unwrap();
+        assert_eq!(detail.employee.salary, 1100.0);
+        assert_eq!(titles(&detail.tasks), ["Ship"]);
+        assert_eq!(detail.events.len(), 1);
+        assert_eq!(detail.events[0].attendees.len(), 1);
+        assert_eq!(detail.salary_history.len(), 1);
+        assert_eq!(detail.payment_history.len(), 1);
+
+        assert!(storage.get_employee_detail(&ada.id, &other.id).unwrap().is_none());
+        storage.delete_employee(&ada.id, &client.id).unwrap();
+        assert!(storage.get_employee_detail(&ada.id, &client.id).unwrap().is_none());
+    }
+}
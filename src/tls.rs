@@ -0,0 +1,46 @@
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use std::fs::File;
+use std::io::BufReader;
+
+pub fn load_server_config(cert_path: &str, key_path: &str) -> Result<ServerConfig, String> {
+    let certs = load_certs(cert_path)?;
+    let key = load_private_key(key_path)?;
+
+    ServerConfig::builder_with_provider(rustls::crypto::ring::default_provider().into())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to configure TLS: {}", e))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Invalid TLS certificate or key ({}, {}): {}", cert_path, key_path, e))
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open TLS certificate {}: {}", path, e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read TLS certificate {}: {}", path, e))?;
+
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {}", path));
+    }
+    Ok(certs)
+}
+
+fn load_private_key(path: &str) -> Result<PrivateKeyDer<'static>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open TLS key {}: {}", path, e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| format!("Failed to read TLS key {}: {}", path, e))?
+        .ok_or_else(|| format!("No private key found in {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_certificate_is_reported_by_path() {
+        let err = load_server_config("/nonexistent/qads-cert.pem", "/nonexistent/qads-key.pem").unwrap_err();
+        assert!(err.starts_with("Failed to open TLS certificate /nonexistent/qads-cert.pem"), "{}", err);
+    }
+}
@@ -0,0 +1,63 @@
+use rust_xlsxwriter::{Color, Format, FormatBorder, Workbook, XlsxError};
+use crate::models::Employee;
+
+pub const XLSX_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+pub fn employees_to_xlsx(employees: &[Employee]) -> Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Employees")?;
+
+    let header = Format::new()
+        .set_bold()
+        .set_font_color(Color::White)
+        .set_background_color(Color::RGB(0x1F4E78))
+        .set_border(FormatBorder::Thin);
+    let currency = Format::new().set_num_format("#,##0.00");
+
+    let columns = [("Name", 30.0), ("Title", 25.0), ("Status", 15.0), ("Salary", 15.0), ("Paid", 8.0), ("Termination Date", 18.0), ("Created At", 22.0)];
+    for (col, (title, width)) in columns.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *title, &header)?;
+        sheet.set_column_width(col as u16, *width)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+
+    for (i, employee) in employees.iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_string(row, 0, &employee.name)?;
+        sheet.write_string(row, 1, &employee.title)?;
+        sheet.write_string(row, 2, &employee.status)?;
+        sheet.write_number_with_format(row, 3, employee.salary, &currency)?;
+        sheet.write_boolean(row, 4, employee.paid)?;
+        if let Some(date) = &employee.termination_date {
+            sheet.write_string(row, 5, date)?;
+        }
+        sheet.write_string(row, 6, employee.created_at.format("%Y-%m-%d %H:%M:%S").to_string())?;
+    }
+
+    workbook.save_to_buffer()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{Data, Reader, Xlsx};
+    use std::io::Cursor;
+
+    #[test]
+    fn workbook_has_a_header_and_one_row_per_employee() {
+        let employees = vec![
+            Employee::new("c1".to_string(), "Ada".to_string(), "Engineer".to_string(), 1234.5, "Active".to_string()),
+            Employee::new("c1".to_string(), "Bob".to_string(), "Clerk".to_string(), 900.0, "Terminated".to_string()),
+        ];
+        let bytes = employees_to_xlsx(&employees).unwrap();
+        assert!(bytes.starts_with(b"PK"));
+
+        let mut workbook: Xlsx<_> = Xlsx::new(Cursor::new(bytes)).unwrap();
+        let range = workbook.worksheet_range("Employees").unwrap();
+        assert_eq!(range.height(), 3);
+        assert_eq!(range.get((0, 0)), Some(&Data::String("Name".to_string())));
+        assert_eq!(range.get((1, 0)), Some(&Data::String("Ada".to_string())));
+        assert_eq!(range.get((1, 3)), Some(&Data::Float(1234.5)));
+    }
+}